@@ -0,0 +1,215 @@
+//! Times building the index, extending letter mappings and the search on a few representative
+//! phrases.
+//!
+//! Run with `cargo bench`. Each step is run several times and the fastest run is reported,
+//! which is steadier than the mean on a busy machine.
+
+use cryptid::{Phrase, Solver};
+use hashbrown::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+const RUNS: usize = 5;
+
+/// Phrases with their work budgets. Short words have huge candidate sets, so phrases made of
+/// them are budgeted to keep each run to a few seconds at most.
+const PHRASES: &[(&str, Option<usize>)] = &[
+    ("JHOSUHQHRY HJ RDM TURHOWRM JASDHJRHQWRHAK", None),
+    (
+        "WRHHKWW XW AIO YXAVM YVXMRSK XW AIO YVOVM XO XW OJK HIRSVCK OI HIAOXARK OJVO HIRAOW",
+        None,
+    ),
+    ("NIJBVO OBJO YAVWJB ABVB", None),
+    ("VOF ZIA GDB XM MBB VOF PWA", Some(1_000_000)),
+    ("QV QX TX QS RF OTX QW NW QV VN AF", Some(1_000_000)),
+];
+
+fn main() {
+    let words: Vec<_> = include_str!("../resources/enable1.txt")
+        .split_whitespace()
+        .collect();
+
+    // Patterns were once numbered through a hash map; they now use a fixed table. Both are
+    // timed here, on the whole word list, so that the difference can be checked.
+    let (elapsed, with_hash_map) = fastest(|| {
+        words
+            .iter()
+            .map(|word| pattern_with_hash_map(word))
+            .collect::<Vec<_>>()
+    });
+    println!("{:>12.3?} patterns with a hash map", elapsed);
+    let (elapsed, with_table) = fastest(|| {
+        words
+            .iter()
+            .map(|word| pattern_with_table(word))
+            .collect::<Vec<_>>()
+    });
+    println!("{:>12.3?} patterns with a table", elapsed);
+    assert_eq!(with_hash_map, with_table);
+
+    // Likewise, the search once extended mappings held in hash maps; it now uses arrays. Each
+    // extends a mapping for "the" with every word against every cipher word of the phrases.
+    let encrypted_words: Vec<_> = PHRASES
+        .iter()
+        .flat_map(|(phrase, _)| phrase.split_whitespace())
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let extensions = |extend: &dyn Fn(&str, &str) -> bool| {
+        let mut extended = 0;
+        for encrypted_word in &encrypted_words {
+            for word in words
+                .iter()
+                .filter(|word| word.len() == encrypted_word.len())
+            {
+                extended += extend(word, encrypted_word) as usize;
+            }
+        }
+        extended
+    };
+
+    let base: HashMap<_, _> = b"xli".iter().copied().zip(b"the".iter().copied()).collect();
+    let (elapsed, with_hash_map) = fastest(|| {
+        extensions(&|word, encrypted_word| extend_hash_map(word, encrypted_word, &base).is_some())
+    });
+    println!("{:>12.3?} mappings with a hash map", elapsed);
+    let base = ArrayMapping::from_hash_map(&base);
+    let (elapsed, with_arrays) =
+        fastest(|| extensions(&|word, encrypted_word| base.extend(word, encrypted_word).is_some()));
+    println!("{:>12.3?} mappings with arrays", elapsed);
+    assert_eq!(with_hash_map, with_arrays);
+
+    let (elapsed, solver) = fastest(|| Solver::from_dictionary(&words));
+    println!("{:>12.3?} index of {} words", elapsed, words.len());
+
+    for &(phrase, budget) in PHRASES {
+        let phrase = Phrase::from_str(phrase).unwrap();
+        let (elapsed, count) = fastest(|| match budget {
+            Some(budget) => solver.solve_with_budget(&phrase, budget).count(),
+            None => solver.solve(&phrase).count(),
+        });
+
+        println!("{:>12.3?} {:>8} {}", elapsed, count, phrase.as_ref());
+    }
+}
+
+/// Runs a step `RUNS` times, returning the fastest time and the last result.
+fn fastest<T>(mut step: impl FnMut() -> T) -> (Duration, T) {
+    let mut fastest = Duration::MAX;
+    let mut result = None;
+
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        result = Some(step());
+        fastest = fastest.min(start.elapsed());
+    }
+
+    (fastest, result.unwrap())
+}
+
+/// Numbers the letters of a word in order of first appearance, looking each up in a hash map.
+fn pattern_with_hash_map(word: &str) -> Vec<u8> {
+    let mut symbols = HashMap::new();
+    word.bytes()
+        .map(|u| {
+            let next = symbols.len() as u8;
+            *symbols.entry(u).or_insert(next)
+        })
+        .collect()
+}
+
+/// Numbers the letters of a word as `pattern_with_hash_map` does, looking each up in a table
+/// indexed by byte, as the solver does.
+fn pattern_with_table(word: &str) -> Vec<u8> {
+    let mut next_symbol = 1;
+    let mut symbol_map = [0u8; 256];
+    word.bytes()
+        .map(|u| {
+            let symbol = &mut symbol_map[u as usize];
+            if *symbol == 0 {
+                *symbol = next_symbol;
+                next_symbol += 1;
+            }
+            *symbol - 1
+        })
+        .collect()
+}
+
+/// Extends a mapping with a cipher word and a candidate word, as the search once did: the new
+/// pairs are gathered in a hash map, the old ones merged in, and the plaintext letters collected
+/// in a set to check that no two cipher letters share one.
+fn extend_hash_map(
+    word: &str,
+    encrypted_word: &str,
+    mapping: &HashMap<u8, u8>,
+) -> Option<HashMap<u8, u8>> {
+    let mut new_mapping = HashMap::new();
+
+    for (u_encoded, u_decoded) in encrypted_word.bytes().zip(word.bytes()) {
+        if let Some(&mapped_char) = new_mapping.get(&u_encoded) {
+            if mapped_char != u_decoded {
+                return None;
+            }
+        }
+
+        if let Some(&mapped_char) = mapping.get(&u_encoded) {
+            if mapped_char != u_decoded {
+                return None;
+            }
+        }
+
+        new_mapping.insert(u_encoded, u_decoded);
+    }
+
+    mapping.iter().for_each(|(&k, &v)| {
+        new_mapping.entry(k).or_insert(v);
+    });
+
+    let value_set: HashSet<u8> = new_mapping.values().cloned().collect();
+    if value_set.len() != new_mapping.len() {
+        return None;
+    }
+
+    Some(new_mapping)
+}
+
+/// A mapping held in arrays indexed by byte, in both directions, as the search holds it.
+#[derive(Clone, Copy)]
+struct ArrayMapping {
+    plain_for: [Option<u8>; 256],
+    cipher_for: [Option<u8>; 256],
+}
+
+impl ArrayMapping {
+    fn from_hash_map(map: &HashMap<u8, u8>) -> Self {
+        let mut mapping = ArrayMapping {
+            plain_for: [None; 256],
+            cipher_for: [None; 256],
+        };
+        for (&cipher, &plain) in map {
+            mapping.plain_for[cipher as usize] = Some(plain);
+            mapping.cipher_for[plain as usize] = Some(cipher);
+        }
+        mapping
+    }
+
+    /// Extends the mapping with a cipher word and a candidate word, as `extend_hash_map` does.
+    fn extend(&self, word: &str, encrypted_word: &str) -> Option<Self> {
+        let mut new_mapping = *self;
+
+        for (cipher, plain) in encrypted_word.bytes().zip(word.bytes()) {
+            match (
+                new_mapping.plain_for[cipher as usize],
+                new_mapping.cipher_for[plain as usize],
+            ) {
+                (Some(mapped), _) if mapped != plain => return None,
+                (Some(_), _) => (),
+                (None, Some(_)) => return None,
+                (None, None) => {
+                    new_mapping.plain_for[cipher as usize] = Some(plain);
+                    new_mapping.cipher_for[plain as usize] = Some(cipher);
+                }
+            }
+        }
+
+        Some(new_mapping)
+    }
+}
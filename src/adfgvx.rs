@@ -0,0 +1,114 @@
+//! Decryption of the ADFGVX cipher, which fractionates each character into a pair of the
+//! letters A, D, F, G, V and X through a 6 by 6 square, then transposes the pairs by columns.
+
+use crate::Solver;
+
+/// Labels for the rows and columns of the square, in order.
+const LABELS: &[u8; 6] = b"adfgvx";
+
+/// Words shorter than this are too easily found by chance in unspaced text to count.
+const MIN_WORD_LEN: usize = 3;
+
+/// Decrypts ADFGVX ciphertext given the square and the transposition key.
+///
+/// The square lists its 36 characters, the letters and digits, row by row. Case and whitespace
+/// in the ciphertext are ignored. Returns `None` if the square is not a permutation of the
+/// letters and digits, the key is empty, or the ciphertext is not a whole number of pairs of
+/// ADFGVX letters.
+pub fn decrypt(ciphertext: &str, square: &str, key: &str) -> Option<String> {
+    let square = square.to_ascii_lowercase().into_bytes();
+    let mut sorted = square.clone();
+    sorted.sort_unstable();
+    let expected: Vec<_> = (b'0'..=b'9').chain(b'a'..=b'z').collect();
+    if sorted != expected || key.is_empty() {
+        return None;
+    }
+
+    let ciphertext: Vec<_> = ciphertext
+        .bytes()
+        .filter(|u| !u.is_ascii_whitespace())
+        .map(|u| {
+            LABELS
+                .iter()
+                .position(|&label| label == u.to_ascii_lowercase())
+        })
+        .collect::<Option<_>>()?;
+    if ciphertext.len() % 2 != 0 {
+        return None;
+    }
+
+    let fractionated = untranspose(&ciphertext, key.as_bytes());
+    Some(
+        fractionated
+            .chunks(2)
+            .map(|pair| square[pair[0] * 6 + pair[1]] as char)
+            .collect(),
+    )
+}
+
+/// Undoes the columnar transposition.
+///
+/// The text was written in rows under the key and read off a column at a time, taking columns
+/// in the alphabetical order of their key letters. Columns at the left of the key hold one more
+/// character than the rest when the text does not fill the last row.
+fn untranspose(text: &[usize], key: &[u8]) -> Vec<usize> {
+    let columns = key.len();
+    let rows = text.len() / columns;
+    let long_columns = text.len() % columns;
+
+    let mut order: Vec<_> = (0..columns).collect();
+    order.sort_by_key(|&column| key[column]);
+
+    let mut by_column = vec![&text[..0]; columns];
+    let mut rest = text;
+    for column in order {
+        let len = rows + usize::from(column < long_columns);
+        let (head, tail) = rest.split_at(len);
+        by_column[column] = head;
+        rest = tail;
+    }
+
+    (0..text.len())
+        .map(|idx| by_column[idx % columns][idx / columns])
+        .collect()
+}
+
+impl<'words> Solver<'words> {
+    /// Decrypts ADFGVX ciphertext as `decrypt` does, scoring the result against the dictionary.
+    ///
+    /// ADFGVX plaintext is written without spaces, so the score is the fraction of its letters
+    /// covered by the best split into dictionary words, from zero to one. Letters and digits that
+    /// fit no word count against it.
+    pub fn decrypt_adfgvx(
+        &self,
+        ciphertext: &str,
+        square: &str,
+        key: &str,
+    ) -> Option<(String, f64)> {
+        let plaintext = decrypt(ciphertext, square, key)?;
+        let coverage = self.word_coverage(&plaintext);
+        Some((plaintext, coverage))
+    }
+
+    /// Finds the fraction of unspaced text that can be covered by dictionary words.
+    pub(crate) fn word_coverage(&self, text: &str) -> f64 {
+        if text.is_empty() {
+            return 0.0;
+        }
+
+        // covered[i] is the most letters of the first i that dictionary words can cover.
+        let mut covered = vec![0; text.len() + 1];
+        for end in 1..=text.len() {
+            covered[end] = covered[end - 1];
+            if let Some(last_start) = end.checked_sub(MIN_WORD_LEN) {
+                for start in (0..=last_start).rev() {
+                    if self.contains_word(&text[start..end]) {
+                        covered[end] = covered[end].max(covered[start] + end - start);
+                    }
+                }
+            }
+        }
+
+        covered[text.len()] as f64 / text.len() as f64
+    }
+}
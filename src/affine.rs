@@ -0,0 +1,95 @@
+//! Detection of affine ciphers, which encrypt each letter `x` as `a * x + b` mod 26.
+
+use crate::{rank, Phrase};
+
+/// Multipliers coprime to 26, the only ones for which an affine cipher can be decrypted.
+const MULTIPLIERS: [u8; 12] = [1, 3, 5, 7, 9, 11, 15, 17, 19, 21, 23, 25];
+
+/// Very common English words. Letter frequencies alone are unreliable for short texts, so a
+/// decryption is given credit for each of these it contains.
+const COMMON_WORDS: [&str; 40] = [
+    "a", "all", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "had", "have",
+    "he", "her", "his", "i", "in", "is", "it", "my", "not", "of", "on", "or", "she", "so", "that",
+    "the", "their", "there", "they", "this", "to", "was", "we", "with", "you", "your",
+];
+
+/// Bonus for a decryption consisting entirely of common words.
+const COMMON_WORD_BONUS: f64 = 0.5;
+
+/// Decryptions scoring below this are not considered English.
+const ENGLISH_THRESHOLD: f64 = -3.3;
+
+/// How far the best decryption must lead the next best. With only 312 keys to try, the right
+/// one stands well clear of the rest; when nothing does, the text is probably not affine.
+const MIN_MARGIN: f64 = 0.25;
+
+/// Tries every affine key and returns the one whose decryption scores best as English.
+///
+/// The key is returned as the `(a, b)` used for encryption. Plaintext is itself a trivial affine
+/// cipher, with a key of `(1, 0)`. Detection is heuristic: it returns `None` if no decryption
+/// looks like English or if no single key is clearly better than the others.
+pub fn detect_affine(phrase: &Phrase) -> Option<((u8, u8), String)> {
+    let mut candidates: Vec<_> = MULTIPLIERS
+        .iter()
+        .flat_map(|&a| (0..26).map(move |b| (a, b)))
+        .map(|key| (key, decrypt(phrase.as_ref(), key)))
+        .filter(|(_, text)| has_vowels(text))
+        .map(|(key, text)| {
+            let score = score(&text);
+            (key, text, score)
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let mut candidates = candidates.into_iter();
+    let (key, text, score) = candidates.next()?;
+    let runner_up = candidates
+        .next()
+        .map_or(f64::NEG_INFINITY, |(_, _, score)| score);
+
+    if score >= ENGLISH_THRESHOLD && score - runner_up >= MIN_MARGIN {
+        Some((key, text))
+    } else {
+        None
+    }
+}
+
+/// Decrypts text enciphered with the affine key `(a, b)`.
+fn decrypt(text: &str, (a, b): (u8, u8)) -> String {
+    let a_inverse = (1..26).find(|&n| (a as u32 * n) % 26 == 1).unwrap_or(1);
+
+    text.bytes()
+        .map(|u| {
+            if u.is_ascii_lowercase() {
+                let y = (u - b'a') as u32;
+                let x = a_inverse * (y + 26 - b as u32) % 26;
+                (b'a' + x as u8) as char
+            } else {
+                u as char
+            }
+        })
+        .collect()
+}
+
+/// Tests whether every word has a vowel, which rules out most wrong keys cheaply.
+fn has_vowels(text: &str) -> bool {
+    text.split_whitespace()
+        .all(|word| word.bytes().any(|u| b"aeiouy".contains(&u)))
+}
+
+fn score(text: &str) -> f64 {
+    let (common, total) = text
+        .split_whitespace()
+        .fold((0, 0), |(common, total), word| {
+            (common + COMMON_WORDS.contains(&word) as usize, total + 1)
+        });
+
+    let bonus = if total == 0 {
+        0.0
+    } else {
+        COMMON_WORD_BONUS * common as f64 / total as f64
+    };
+
+    rank::score(text) + bonus
+}
@@ -0,0 +1,88 @@
+//! Statistical checks on ciphertext that can be made before solving.
+
+use crate::{mapping::Mapping, Pattern, Phrase, Solver};
+
+/// The index of coincidence below which text is considered too flat for a simple substitution.
+///
+/// English averages about 0.066 and uniformly random letters about 0.038. A simple substitution
+/// only renames letters, so it leaves the index unchanged.
+const HOMOPHONIC_THRESHOLD: f64 = 0.048;
+
+/// The fewest letters needed before the index of coincidence means much.
+const MIN_LETTERS: usize = 40;
+
+/// Computes the index of coincidence: the chance that two letters drawn from the text without
+/// replacement are the same.
+///
+/// Returns `None` for text with fewer than two letters.
+pub fn index_of_coincidence(text: &str) -> Option<f64> {
+    let mut counts = [0usize; 26];
+    let mut total = 0;
+    for u in text.bytes().filter(u8::is_ascii_lowercase) {
+        counts[(u - b'a') as usize] += 1;
+        total += 1;
+    }
+
+    if total < 2 {
+        return None;
+    }
+
+    let pairs: usize = counts.iter().map(|&n| n * n.saturating_sub(1)).sum();
+    Some(pairs as f64 / (total * (total - 1)) as f64)
+}
+
+/// Tests whether a phrase looks like a homophonic cipher, which this solver cannot handle.
+///
+/// Homophonic ciphers spread common letters across several symbols, so their letter frequencies
+/// are much flatter than those of English under a simple substitution. Phrases too short to
+/// judge are never flagged.
+pub fn is_likely_homophonic(phrase: &Phrase) -> bool {
+    let letters = phrase
+        .as_ref()
+        .bytes()
+        .filter(u8::is_ascii_lowercase)
+        .count();
+
+    letters >= MIN_LETTERS
+        && index_of_coincidence(phrase.as_ref()).is_some_and(|ic| ic < HOMOPHONIC_THRESHOLD)
+}
+
+/// How a single cipher word matches the dictionary, for finding out why it does not match.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WordExplanation<'words> {
+    /// The word's letter pattern, with each distinct letter renamed in order of appearance, so
+    /// that `xyzzy` becomes `abcca`.
+    pub pattern: String,
+    /// The number of dictionary words sharing the pattern.
+    pub candidate_count: usize,
+    /// The first few of those words, in alphabetical order.
+    pub sample: Vec<&'words str>,
+}
+
+impl<'words> Solver<'words> {
+    /// Explains how a cipher word matches the dictionary on its own, without any other words to
+    /// constrain it, listing up to `sample_size` candidates.
+    pub fn explain_word(&self, word: &str, sample_size: usize) -> WordExplanation<'words> {
+        let word = word.to_ascii_lowercase();
+        let pattern = Pattern::from_str(&word)
+            .0
+            .iter()
+            .map(|&symbol| {
+                if symbol < 26 {
+                    (b'a' + symbol) as char
+                } else {
+                    '?'
+                }
+            })
+            .collect();
+
+        let mut candidates = self.find_candidate_matches(&word, &Mapping::default());
+        candidates.sort_unstable();
+
+        WordExplanation {
+            pattern,
+            candidate_count: candidates.len(),
+            sample: candidates.into_iter().take(sample_size).collect(),
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::{key, mapping::Mapping, rank, Constraints, Phrase, Solver};
+use hashbrown::HashSet;
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+/// A partial solution waiting to be explored.
+struct Node<'p> {
+    score: f64,
+    /// Breaks ties in favor of the node created first, to keep the order reproducible.
+    sequence: usize,
+    mapping: Mapping,
+    remaining: Vec<&'p str>,
+}
+
+impl PartialEq for Node<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node<'_> {}
+
+impl PartialOrd for Node<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.score
+            .total_cmp(&other.score)
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+/// Yields solutions best first, as far as the letters decided so far can tell.
+///
+/// Partial solutions wait in a priority queue ordered by how English the letters they have
+/// decided look. Each step expands the most promising one, guessing the cipher word with the
+/// fewest candidates, and a solution is yielded when a complete one reaches the front.
+pub struct RankedSolutions<'s, 'words, 'p> {
+    solver: &'s Solver<'words>,
+    phrase: &'p Phrase,
+    queue: BinaryHeap<Node<'p>>,
+    sequence: usize,
+}
+
+impl<'s, 'words, 'p> RankedSolutions<'s, 'words, 'p> {
+    fn push(&mut self, mapping: Mapping, remaining: Vec<&'p str>) {
+        let score = partial_score(self.phrase, &mapping);
+        self.queue.push(Node {
+            score,
+            sequence: self.sequence,
+            mapping,
+            remaining,
+        });
+        self.sequence += 1;
+    }
+}
+
+impl Iterator for RankedSolutions<'_, '_, '_> {
+    type Item = (String, f64);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.queue.pop() {
+            let mut candidates: Vec<_> = node
+                .remaining
+                .iter()
+                .map(|&word| {
+                    let candidates = self.solver.find_candidate_matches(word, &node.mapping);
+                    (word, candidates)
+                })
+                .collect();
+
+            let (idx, _) = match candidates
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, (word, candidates))| (candidates.len(), *word))
+            {
+                Some(best) => best,
+                None => {
+                    let solution = self.phrase.render(&node.mapping.to_hash_map());
+                    return Some((solution, node.score));
+                }
+            };
+
+            let (encrypted_word, candidate_words) = candidates.swap_remove(idx);
+            let remaining: Vec<_> = candidates.into_iter().map(|(word, _)| word).collect();
+
+            let mut candidate_words: Vec<_> = candidate_words.into_iter().collect();
+            candidate_words.sort_unstable();
+
+            for word in candidate_words {
+                if let Some(mapping) = key::try_extend_mapping(
+                    &Constraints::default(),
+                    word,
+                    encrypted_word,
+                    &node.mapping,
+                ) {
+                    self.push(mapping, remaining.clone());
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Scores the letters a partial mapping decides, wherever they occur in the phrase.
+fn partial_score(phrase: &Phrase, mapping: &Mapping) -> f64 {
+    let decided: String = phrase
+        .as_ref()
+        .bytes()
+        .filter_map(|u| mapping.get(u).map(|u| u as char))
+        .collect();
+    rank::score(&decided)
+}
+
+impl<'words> Solver<'words> {
+    /// Lazily yields solutions in approximately ranked order, with their scores.
+    ///
+    /// Unlike `solve_ranked`, this does not find every solution before yielding the first, so
+    /// taking only the first few is cheap. The order is a best guess: it follows the score of
+    /// the letters decided at each step, which can differ from the final ranking.
+    pub fn solve_ranked_lazily<'s, 'p>(
+        &'s self,
+        phrase: &'p Phrase,
+    ) -> RankedSolutions<'s, 'words, 'p> {
+        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
+        let mut encrypted_words: Vec<_> = encrypted_words.into_iter().collect();
+        encrypted_words.sort_unstable();
+
+        let mut solutions = RankedSolutions {
+            solver: self,
+            phrase,
+            queue: BinaryHeap::new(),
+            sequence: 0,
+        };
+        solutions.push(Mapping::default(), encrypted_words);
+        solutions
+    }
+}
@@ -0,0 +1,254 @@
+use crate::{Phrase, Solver};
+use hashbrown::HashMap;
+use std::{
+    convert::TryFrom,
+    io::{self, Read, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+/// Identifies a cache file, followed by the format version.
+const MAGIC: &[u8; 8] = b"CRYPTID\x01";
+
+/// The shape of a phrase, independent of which letters it uses.
+///
+/// Letters are numbered in order of first appearance and whitespace is kept as a separator, so
+/// two phrases share a signature exactly when one can be turned into the other by renaming
+/// letters. Such phrases have the same solutions.
+#[derive(Debug, Eq, PartialEq, Hash)]
+struct Signature(Vec<u8>);
+
+impl Signature {
+    const SEPARATOR: u8 = u8::MAX;
+
+    /// Computes the signature of a phrase, along with its letters in order of first appearance.
+    fn of(phrase: &Phrase) -> (Signature, Vec<u8>) {
+        let mut letters = Vec::new();
+        let symbols = phrase
+            .as_ref()
+            .bytes()
+            .map(|u| {
+                if u.is_ascii_whitespace() {
+                    return Signature::SEPARATOR;
+                }
+
+                match letters.iter().position(|&letter| letter == u) {
+                    Some(idx) => idx as u8,
+                    None => {
+                        letters.push(u);
+                        (letters.len() - 1) as u8
+                    }
+                }
+            })
+            .collect();
+
+        (Signature(symbols), letters)
+    }
+
+    /// Counts the distinct letters of the phrases with this signature.
+    fn letter_count(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|&&symbol| symbol != Signature::SEPARATOR)
+            .max()
+            .map_or(0, |&symbol| symbol as usize + 1)
+    }
+}
+
+/// Solutions for phrases seen before, keyed by signature.
+///
+/// Each solution is stored as the plaintext letter for each letter of the signature, so it can
+/// be turned back into a key for any phrase of the same shape.
+#[derive(Debug, Default)]
+pub(crate) struct SolutionCache {
+    entries: Mutex<HashMap<Signature, Vec<Vec<u8>>>>,
+    hits: AtomicUsize,
+}
+
+impl SolutionCache {
+    pub(crate) fn get_or_solve(
+        &self,
+        phrase: &Phrase,
+        solve: impl FnOnce() -> Vec<HashMap<u8, u8>>,
+    ) -> Vec<HashMap<u8, u8>> {
+        let (signature, letters) = Signature::of(phrase);
+
+        let cached = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&signature)
+            .map(|solutions| {
+                solutions
+                    .iter()
+                    .map(|plain| letters.iter().copied().zip(plain.iter().copied()).collect())
+                    .collect()
+            });
+
+        if let Some(mappings) = cached {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return mappings;
+        }
+
+        let mappings = solve();
+        let solutions = mappings
+            .iter()
+            .map(|mapping| letters.iter().map(|u| mapping[u]).collect())
+            .collect();
+
+        self.entries.lock().unwrap().insert(signature, solutions);
+        mappings
+    }
+}
+
+impl SolutionCache {
+    /// Writes the cache in a compact binary form.
+    ///
+    /// Lengths and counts are little-endian `u32`s. After the magic number and the dictionary
+    /// fingerprint comes the entry count, then each signature followed by its solutions.
+    fn write(&self, fingerprint: u64, mut out: impl Write) -> io::Result<()> {
+        let entries = self.entries.lock().unwrap();
+
+        out.write_all(MAGIC)?;
+        out.write_all(&fingerprint.to_le_bytes())?;
+        write_len(&mut out, entries.len())?;
+        for (signature, solutions) in entries.iter() {
+            write_bytes(&mut out, &signature.0)?;
+            write_len(&mut out, solutions.len())?;
+            for solution in solutions {
+                write_bytes(&mut out, solution)?;
+            }
+        }
+
+        out.flush()
+    }
+
+    /// Reads a cache written by `write`.
+    ///
+    /// Returns `None` if the cache was built from a different dictionary, since its solutions
+    /// would not hold for this one.
+    fn read(fingerprint: u64, mut input: impl Read) -> io::Result<Option<SolutionCache>> {
+        let mut magic = [0; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a solution cache",
+            ));
+        }
+
+        let mut stored_fingerprint = [0; 8];
+        input.read_exact(&mut stored_fingerprint)?;
+        if u64::from_le_bytes(stored_fingerprint) != fingerprint {
+            return Ok(None);
+        }
+
+        let mut entries = HashMap::new();
+        for _ in 0..read_len(&mut input)? {
+            let signature = Signature(read_bytes(&mut input)?);
+            let solutions: Vec<_> = (0..read_len(&mut input)?)
+                .map(|_| read_bytes(&mut input))
+                .collect::<io::Result<_>>()?;
+
+            // Each solution gives a plaintext letter for every letter of the signature.
+            if solutions
+                .iter()
+                .any(|solution| solution.len() != signature.letter_count())
+            {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "cached solution does not fit its phrase",
+                ));
+            }
+            entries.insert(signature, solutions);
+        }
+
+        Ok(Some(SolutionCache {
+            entries: Mutex::new(entries),
+            hits: AtomicUsize::new(0),
+        }))
+    }
+}
+
+fn write_len(out: &mut impl Write, len: usize) -> io::Result<()> {
+    let len = u32::try_from(len)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "cache entry too large"))?;
+    out.write_all(&len.to_le_bytes())
+}
+
+fn write_bytes(out: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_len(out, bytes.len())?;
+    out.write_all(bytes)
+}
+
+fn read_len(input: &mut impl Read) -> io::Result<usize> {
+    let mut len = [0; 4];
+    input.read_exact(&mut len)?;
+    Ok(u32::from_le_bytes(len) as usize)
+}
+
+fn read_bytes(input: &mut impl Read) -> io::Result<Vec<u8>> {
+    // A corrupt length could be anything, so only as much is allocated as there is to read.
+    let len = read_len(input)?;
+    let mut bytes = Vec::new();
+    input.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "cache entry cut short",
+        ));
+    }
+    Ok(bytes)
+}
+
+impl<'words> Solver<'words> {
+    /// Enables caching solutions by phrase signature.
+    ///
+    /// Phrases that differ only in which letters they use have the same solutions, so once one
+    /// has been solved, the others need only be re-rendered. This pays off when solving batches
+    /// of phrases with repetitive structure.
+    pub fn with_cache(mut self) -> Self {
+        self.cache = Some(SolutionCache::default());
+        self
+    }
+
+    /// Enables caching, starting from a cache saved by `write_cache`.
+    ///
+    /// A cache saved with a different dictionary is ignored, leaving an empty cache.
+    pub fn with_cache_from(mut self, input: impl Read) -> io::Result<Self> {
+        let cache = SolutionCache::read(self.dictionary_fingerprint(), input)?;
+        self.cache = Some(cache.unwrap_or_default());
+        Ok(self)
+    }
+
+    /// Saves the cache so that later runs can start from it. Writes nothing if caching is off.
+    pub fn write_cache(&self, out: impl Write) -> io::Result<()> {
+        match &self.cache {
+            Some(cache) => cache.write(self.dictionary_fingerprint(), out),
+            None => Ok(()),
+        }
+    }
+
+    /// Hashes the dictionary, independent of word order, to tell whether a saved cache applies.
+    fn dictionary_fingerprint(&self) -> u64 {
+        // FNV-1a, combined across words with a wrapping sum so that order does not matter.
+        self.words_by_pattern
+            .values()
+            .flatten()
+            .map(|word| {
+                word.bytes().fold(0xcbf2_9ce4_8422_2325, |hash: u64, u| {
+                    (hash ^ u as u64).wrapping_mul(0x0000_0100_0000_01b3)
+                })
+            })
+            .fold(0, u64::wrapping_add)
+    }
+
+    /// Counts the solves answered from the cache.
+    pub fn cache_hits(&self) -> usize {
+        self.cache
+            .as_ref()
+            .map_or(0, |cache| cache.hits.load(Ordering::Relaxed))
+    }
+}
@@ -0,0 +1,42 @@
+use crate::{Constraints, Phrase, Solver};
+use hashbrown::HashMap;
+use std::{
+    cell::Cell,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread,
+};
+
+/// Solutions that may wait in the channel before the search pauses for the consumer.
+const CHANNEL_CAPACITY: usize = 64;
+
+impl Solver<'static> {
+    /// Searches for solutions on a background thread, sending each one as it is found.
+    ///
+    /// The channel holds only a few solutions, so a slow consumer holds the search back rather
+    /// than letting solutions pile up. The channel closes once the search is complete, and
+    /// dropping the receiver stops the search early.
+    pub fn solve_channel(self: &Arc<Self>, phrase: &Phrase) -> Receiver<String> {
+        let (sender, receiver) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let solver = Arc::clone(self);
+        let phrase = Phrase(phrase.0.clone());
+
+        thread::spawn(move || {
+            let cancelled = Cell::new(false);
+            let constraints = Constraints {
+                cancelled: Some(&cancelled),
+                ..Constraints::default()
+            };
+
+            solver.visit_phrases(&constraints, HashMap::new(), &[&phrase], &mut |mapping| {
+                if sender.send(phrase.render(&mapping)).is_err() {
+                    cancelled.set(true);
+                }
+            });
+        });
+
+        receiver
+    }
+}
@@ -0,0 +1,109 @@
+//! Experimental solving of a columnar transposition layered over a substitution.
+//!
+//! The plaintext, spaces included, is written into rows of a fixed width and read back out a
+//! column at a time in the order given by a key. The result is then put through a substitution.
+//! Undoing every short transposition and solving what comes out recovers both.
+
+use crate::{rank, Phrase, Solver};
+
+/// The most attempts to extend a mapping spent on any one arrangement. Wrong arrangements can
+/// produce text with enormous numbers of nonsense solutions, which must not stall the search.
+const ARRANGEMENT_BUDGET: usize = 100_000;
+
+/// The best solution found for a combined cipher.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Combo {
+    /// The order in which the columns were read out during encryption.
+    pub columns: Vec<usize>,
+    pub solution: String,
+    pub score: f64,
+}
+
+impl<'words> Solver<'words> {
+    /// Solves a columnar transposition combined with a substitution.
+    ///
+    /// Every column order for up to `max_columns` columns is tried, so this is only practical
+    /// for small column counts. Arrangements that do not split into words the dictionary could
+    /// match are skipped; the rest are solved and the best scoring solution is returned.
+    pub fn solve_combo(&self, phrase: &Phrase, max_columns: usize) -> Option<Combo> {
+        let text = phrase.as_ref().as_bytes();
+        let mut best: Option<Combo> = None;
+
+        for width in 2..=max_columns.min(text.len()) {
+            for columns in permutations(width) {
+                let candidate = Phrase(untranspose(text, &columns));
+                if !is_well_spaced(candidate.as_ref())
+                    || self.estimate_solution_count(&candidate) == 0
+                {
+                    continue;
+                }
+
+                for solution in self.solve_with_budget(&candidate, ARRANGEMENT_BUDGET) {
+                    let score = rank::score(&solution) + rank::short_word_bonus(&solution);
+                    if best.as_ref().is_none_or(|best| score > best.score) {
+                        best = Some(Combo {
+                            columns: columns.clone(),
+                            solution,
+                            score,
+                        });
+                    }
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Reverses a columnar transposition with the given column order.
+///
+/// When the text does not fill the last row, the leftmost columns are the ones holding an extra
+/// character.
+fn untranspose(text: &[u8], columns: &[usize]) -> String {
+    let width = columns.len();
+    let rows = text.len().div_ceil(width);
+    let long_columns = match text.len() % width {
+        0 => width,
+        n => n,
+    };
+
+    let mut grid = vec![0u8; text.len()];
+    let mut remaining = text;
+    for &column in columns {
+        let height = if column < long_columns {
+            rows
+        } else {
+            rows - 1
+        };
+        let (read, rest) = remaining.split_at(height);
+        for (row, &u) in read.iter().enumerate() {
+            grid[row * width + column] = u;
+        }
+        remaining = rest;
+    }
+
+    grid.into_iter().map(char::from).collect()
+}
+
+/// Tests whether text consists of words separated by single spaces.
+fn is_well_spaced(text: &str) -> bool {
+    !text.starts_with(' ') && !text.ends_with(' ') && !text.contains("  ")
+}
+
+/// Lists every ordering of `0..n`.
+fn permutations(n: usize) -> Vec<Vec<usize>> {
+    if n == 0 {
+        return vec![Vec::new()];
+    }
+
+    permutations(n - 1)
+        .into_iter()
+        .flat_map(|shorter| {
+            (0..n).map(move |idx| {
+                let mut permutation = shorter.clone();
+                permutation.insert(idx, n - 1);
+                permutation
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,142 @@
+//! Solving cryptarithms, in which each letter of a sum stands for a different digit, such as
+//! `SEND + MORE = MONEY`.
+
+use crate::{Phrase, PhraseError};
+use hashbrown::HashMap;
+use std::{error::Error, fmt};
+
+/// The reason an equation could not be read as a cryptarithm.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CryptarithmError {
+    /// The equation is not of the form `a + b + ... = c`.
+    Malformed,
+    /// A term contains something other than letters.
+    Term(PhraseError),
+    /// The equation uses more distinct letters than there are digits.
+    TooManyLetters,
+    /// A term has too many letters for its place values to be represented.
+    TermTooLong,
+}
+
+impl fmt::Display for CryptarithmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CryptarithmError::Malformed => f.write_str("expected an equation like a + b = c"),
+            CryptarithmError::Term(e) => write!(f, "invalid term: {}", e),
+            CryptarithmError::TooManyLetters => f.write_str("more than ten distinct letters"),
+            CryptarithmError::TermTooLong => f.write_str("a term is too long to add up"),
+        }
+    }
+}
+
+impl Error for CryptarithmError {}
+
+/// Finds every assignment of distinct digits to letters that makes a sum true.
+///
+/// The equation is any number of words joined by `+`, then `=` and one more word, with letters
+/// in either case. As usual, the first letter of a word of more than one letter may not be
+/// zero. Each solution maps lowercase letters to digit values, from 0 to 9.
+pub fn solve_cryptarithm(equation: &str) -> Result<Vec<HashMap<u8, u8>>, CryptarithmError> {
+    let (addends, sum) = equation
+        .split_once('=')
+        .ok_or(CryptarithmError::Malformed)?;
+    let sum = term(sum)?;
+    let addends = addends
+        .split('+')
+        .map(term)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Each letter's coefficient is its place value summed over the addends, less its place
+    // value in the sum, so a solution is exactly an assignment where the total comes to zero.
+    let mut coefficients: HashMap<u8, i64> = HashMap::new();
+    let mut leading = Vec::new();
+    for (word, sign) in addends.iter().map(|word| (word, 1)).chain(Some((&sum, -1))) {
+        let word = word.as_ref();
+        let mut place = sign;
+        for u in word.bytes().rev() {
+            let coefficient = coefficients.entry(u).or_default();
+            *coefficient = coefficient
+                .checked_add(place)
+                .ok_or(CryptarithmError::TermTooLong)?;
+            place = place.checked_mul(10).ok_or(CryptarithmError::TermTooLong)?;
+        }
+        if word.len() > 1 {
+            leading.push(word.as_bytes()[0]);
+        }
+    }
+
+    if coefficients.len() > 10 {
+        return Err(CryptarithmError::TooManyLetters);
+    }
+
+    // Deciding the letters with the largest coefficients first prunes the search soonest.
+    let mut letters: Vec<_> = coefficients
+        .into_iter()
+        .map(|(u, coefficient)| (u, coefficient, leading.contains(&u)))
+        .collect();
+    letters.sort_by_key(|&(u, coefficient, _)| (std::cmp::Reverse(coefficient.abs()), u));
+
+    let mut solutions = Vec::new();
+    assign(
+        &letters,
+        0,
+        &mut [false; 10],
+        &mut Vec::new(),
+        &mut solutions,
+    );
+    Ok(solutions)
+}
+
+/// Reads one word of an equation.
+fn term(s: &str) -> Result<Phrase, CryptarithmError> {
+    let phrase = Phrase::from_str(s.trim()).map_err(CryptarithmError::Term)?;
+    match phrase.as_ref() {
+        word if word.is_empty() || word.contains(char::is_whitespace) => {
+            Err(CryptarithmError::Malformed)
+        }
+        _ => Ok(phrase),
+    }
+}
+
+/// Assigns digits to the remaining letters, recording each complete assignment that balances.
+fn assign(
+    letters: &[(u8, i64, bool)],
+    total: i128,
+    used: &mut [bool; 10],
+    digits: &mut Vec<u8>,
+    solutions: &mut Vec<HashMap<u8, u8>>,
+) {
+    let idx = digits.len();
+    if idx == letters.len() {
+        if total == 0 {
+            let solution = letters.iter().map(|&(u, ..)| u).zip(digits.iter().copied());
+            solutions.push(solution.collect());
+        }
+        return;
+    }
+
+    // The letters still to come can move the total only so far in either direction. Totals are
+    // wider than coefficients so that this cannot overflow, however long the terms.
+    let reach: i128 = letters[idx + 1..]
+        .iter()
+        .map(|&(_, coefficient, _)| i128::from(coefficient).abs() * 9)
+        .sum();
+
+    let (_, coefficient, leading) = letters[idx];
+    for digit in u8::from(leading)..10 {
+        if used[digit as usize] {
+            continue;
+        }
+
+        let total = total + i128::from(coefficient) * i128::from(digit);
+        if total.abs() > reach {
+            continue;
+        }
+
+        used[digit as usize] = true;
+        digits.push(digit);
+        assign(letters, total, used, digits, solutions);
+        digits.pop();
+        used[digit as usize] = false;
+    }
+}
@@ -0,0 +1,37 @@
+use cryptid::{key, Phrase};
+use std::io::{self, Write};
+
+/// Writes ranked solutions as CSV, one row per solution.
+///
+/// Columns are the phrase, the solution, its score, the key and the number of words. The key is
+/// written as `cipher:plain` pairs in cipher letter order.
+pub fn write(mut out: impl Write, phrase: &Phrase, solutions: &[(String, f64)]) -> io::Result<()> {
+    writeln!(out, "phrase,solution,score,key,word_count")?;
+
+    for (solution, score) in solutions {
+        let key = key::solution_to_key(phrase.as_ref(), solution)
+            .map(|key| key::key_pairs(&key))
+            .unwrap_or_default();
+
+        writeln!(
+            out,
+            "{},{},{:.4},{},{}",
+            escape(phrase.as_ref()),
+            escape(solution),
+            score,
+            escape(&key),
+            solution.split_whitespace().count(),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a field if it contains a delimiter, quote or line break, doubling any quotes.
+fn escape(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
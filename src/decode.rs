@@ -0,0 +1,110 @@
+use crate::options::Decode;
+use std::{borrow::Cow, fmt};
+
+/// An encoding that input was found to be in.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    Base64,
+    Hex,
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Encoding::Base64 => f.write_str("base64"),
+            Encoding::Hex => f.write_str("hex"),
+        }
+    }
+}
+
+/// Decodes input as requested, returning the text along with the encoding it was decoded from,
+/// if any.
+///
+/// In `Auto` mode, input is only decoded if it contains a character no phrase can, such as a
+/// digit, and decodes to printable text; otherwise it is returned unchanged. An explicit
+/// encoding that does not decode to text is an error.
+pub fn decode(input: &str, mode: Decode) -> Result<(Cow<'_, str>, Option<Encoding>), Encoding> {
+    let attempt = |encoding| {
+        let compact: String = input.split_whitespace().collect();
+        let bytes = match encoding {
+            Encoding::Base64 => base64(&compact),
+            Encoding::Hex => hex(&compact),
+        };
+        bytes
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .filter(|text| {
+                text.chars()
+                    .all(|c| c.is_ascii_graphic() || c.is_ascii_whitespace())
+            })
+    };
+
+    let encoding = match mode {
+        Decode::None => return Ok((Cow::Borrowed(input), None)),
+        Decode::Base64 => Encoding::Base64,
+        Decode::Hex => Encoding::Hex,
+        Decode::Auto => {
+            let looks_encoded = input
+                .chars()
+                .any(|c| c.is_ascii_digit() || "+/=".contains(c));
+            if !looks_encoded {
+                return Ok((Cow::Borrowed(input), None));
+            }
+
+            // Hex digits are also base64 digits, so hex is tried first.
+            return Ok([Encoding::Hex, Encoding::Base64]
+                .iter()
+                .find_map(|&encoding| attempt(encoding).map(|text| (text, encoding)))
+                .map_or((Cow::Borrowed(input), None), |(text, encoding)| {
+                    (Cow::Owned(text), Some(encoding))
+                }));
+        }
+    };
+
+    attempt(encoding)
+        .map(|text| (Cow::Owned(text), Some(encoding)))
+        .ok_or(encoding)
+}
+
+fn hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let digit = |u: u8| (u as char).to_digit(16);
+            Some((digit(pair[0])? * 16 + digit(pair[1])?) as u8)
+        })
+        .collect()
+}
+
+fn base64(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=');
+    if s.len() % 4 == 1 {
+        return None;
+    }
+
+    let sextet = |u: u8| match u {
+        b'A'..=b'Z' => Some(u - b'A'),
+        b'a'..=b'z' => Some(u - b'a' + 26),
+        b'0'..=b'9' => Some(u - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    };
+
+    let mut bytes = Vec::with_capacity(s.len() * 3 / 4);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for u in s.bytes() {
+        buffer = buffer << 6 | u32::from(sextet(u)?);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(bytes)
+}
@@ -0,0 +1,109 @@
+use hashbrown::{HashMap, HashSet};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Reads a word list from disk.
+///
+/// The solver works on lowercase text, so the list is lowercased as it is read.
+pub fn read(path: impl AsRef<Path>) -> io::Result<String> {
+    Ok(fs::read_to_string(path)?.to_ascii_lowercase())
+}
+
+/// Reads every `.txt` word list in a directory.
+///
+/// Failing to read the directory itself is an error, but failures for individual files are
+/// returned alongside their paths so that the caller can decide whether they matter.
+pub fn read_dir(path: impl AsRef<Path>) -> io::Result<Vec<(PathBuf, io::Result<String>)>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension() == Some("txt".as_ref()) {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let list = read(&path);
+            (path, list)
+        })
+        .collect())
+}
+
+/// Merges several word lists into one, dropping duplicates.
+///
+/// A list may annotate its words with frequencies by following a word with a number, as in
+/// `the 23135851162`. Given a minimum frequency, annotated words below it are dropped so that
+/// they never take part in matching. Words without a frequency are always kept.
+pub fn merge<'a>(
+    lists: impl IntoIterator<Item = &'a str>,
+    min_frequency: Option<u64>,
+) -> Vec<&'a str> {
+    let mut seen = HashSet::new();
+    lists
+        .into_iter()
+        .flat_map(annotated_words)
+        .filter(|&(_, frequency)| match (min_frequency, frequency) {
+            (Some(min), Some(frequency)) => frequency >= min,
+            _ => true,
+        })
+        .filter(|&(word, _)| seen.insert(word))
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// Picks the `n` most frequent of the given words, according to `frequencies`.
+///
+/// Words without a frequency are ranked after all those with one, in the order given.
+pub fn most_common<'a>(
+    words: &[&'a str],
+    frequencies: &HashMap<&str, u64>,
+    n: usize,
+) -> Vec<&'a str> {
+    let mut words = words.to_vec();
+    words.sort_by_key(|word| std::cmp::Reverse(frequencies.get(word)));
+    words.truncate(n);
+    words
+}
+
+/// Collects the frequencies annotated in several word lists, as described for `merge`.
+///
+/// A word annotated in more than one list keeps its first frequency.
+pub fn frequencies<'a>(lists: impl IntoIterator<Item = &'a str>) -> HashMap<&'a str, u64> {
+    let mut frequencies = HashMap::new();
+    for (word, frequency) in lists.into_iter().flat_map(annotated_words) {
+        if let Some(frequency) = frequency {
+            frequencies.entry(word).or_insert(frequency);
+        }
+    }
+    frequencies
+}
+
+/// Finds the frequency that a given percentage of annotated words fall at or below, or `None`
+/// if no word is annotated.
+///
+/// Keeping only words at least this frequent keeps the words above the percentile.
+pub fn frequency_at_percentile(frequencies: &HashMap<&str, u64>, percentile: f64) -> Option<u64> {
+    let mut sorted: Vec<_> = frequencies.values().copied().collect();
+    sorted.sort_unstable();
+
+    let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (sorted.len() as f64 - 1.0)).round();
+    sorted.get(rank as usize).copied()
+}
+
+/// Pairs each word in a list with the frequency that follows it, if any.
+fn annotated_words(list: &str) -> Vec<(&str, Option<u64>)> {
+    let mut words: Vec<(&str, Option<u64>)> = Vec::new();
+    for token in list.split_whitespace() {
+        match (token.parse(), words.last_mut()) {
+            (Ok(frequency), Some((_, slot @ None))) => *slot = Some(frequency),
+            (Ok(_), _) => (),
+            (Err(_), _) => words.push((token, None)),
+        }
+    }
+    words
+}
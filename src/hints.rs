@@ -0,0 +1,399 @@
+use crate::{key, mapping::Mapping, Constraints, Phrase, Solver};
+use hashbrown::{HashMap, HashSet};
+
+/// How a guessed letter fits the solutions of a phrase.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuessFeedback {
+    /// No solution agrees with the guess.
+    Inconsistent,
+    /// Some solution agrees with the guess, but others may not.
+    Consistent,
+    /// The guess is the only possibility left.
+    Forced,
+}
+
+/// Information gained when two words must agree on a letter: the chance that two letters of
+/// English text match is about one in fifteen.
+const LETTER_AGREEMENT_BITS: f64 = 3.9;
+
+impl<'words> Solver<'words> {
+    /// Solves a phrase given some known letter mappings, from cipher letter to plaintext letter.
+    ///
+    /// Hints that would map two cipher letters to the same plaintext letter admit no solutions.
+    pub fn solve_with_hints<'a>(
+        &self,
+        phrase: &'a Phrase,
+        hints: &HashMap<u8, u8>,
+    ) -> impl Iterator<Item = String> + 'a {
+        self.solve_mappings_with_hints(phrase, hints)
+            .into_iter()
+            .map(move |mapping| phrase.render(&mapping))
+    }
+
+    /// Finds every letter mapping consistent with both the dictionary and the given hints.
+    pub fn solve_mappings_with_hints(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+    ) -> Vec<HashMap<u8, u8>> {
+        let plaintext: HashSet<_> = hints.values().collect();
+        if plaintext.len() != hints.len() {
+            return Vec::new();
+        }
+
+        self.guess_phrase(&Constraints::default(), hints.clone(), phrase)
+    }
+
+    /// Solves a phrase given the plaintext letters at some of its positions, as a puzzle reveals
+    /// squares.
+    ///
+    /// Positions count characters of the phrase from zero, spaces included. Positions past the
+    /// end or on a space are ignored. Every occurrence of a revealed cipher letter is fixed with
+    /// it, and reveals that disagree about a cipher letter admit no solutions.
+    pub fn solve_with_revealed(&self, phrase: &Phrase, revealed: &[(usize, char)]) -> Vec<String> {
+        let text = phrase.as_ref().as_bytes();
+        let mut hints = HashMap::new();
+
+        for &(idx, plain) in revealed {
+            let cipher = match text.get(idx) {
+                Some(&u) if u.is_ascii_lowercase() => u,
+                _ => continue,
+            };
+            let plain = match plain.to_ascii_lowercase() {
+                plain @ 'a'..='z' => plain as u8,
+                _ => return Vec::new(),
+            };
+
+            if hints
+                .insert(cipher, plain)
+                .is_some_and(|mapped| mapped != plain)
+            {
+                return Vec::new();
+            }
+        }
+
+        self.solve_mappings_with_hints(phrase, &hints)
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect()
+    }
+
+    /// Tests whether a phrase still has a solution once a hint is added to some existing hints.
+    ///
+    /// A hint is a pair of cipher letter and plaintext letter. Hints that contradict the existing
+    /// ones are never viable.
+    pub fn hint_is_viable(
+        &self,
+        phrase: &Phrase,
+        (cipher, plain): (u8, u8),
+        existing: &HashMap<u8, u8>,
+    ) -> bool {
+        match existing.get(&cipher) {
+            Some(&mapped) if mapped != plain => return false,
+            Some(_) => (),
+            None if existing.values().any(|&u| u == plain) => return false,
+            None => (),
+        }
+
+        let mut hints = existing.clone();
+        hints.insert(cipher, plain);
+        !self.solve_mappings_with_hints(phrase, &hints).is_empty()
+    }
+
+    /// Judges a guess, given as a pair of cipher letter and plaintext letter, against the guesses
+    /// already accepted.
+    ///
+    /// A guess is consistent if some solution agrees with it and the existing guesses, and forced
+    /// if the existing guesses leave nothing else, as far as `forced_mappings` can tell.
+    pub fn check_guess(
+        &self,
+        phrase: &Phrase,
+        (cipher, plain): (u8, u8),
+        existing: &HashMap<u8, u8>,
+    ) -> GuessFeedback {
+        if !self.hint_is_viable(phrase, (cipher, plain), existing) {
+            GuessFeedback::Inconsistent
+        } else if existing.get(&cipher) == Some(&plain)
+            || self.forced_mappings(phrase, existing).get(&cipher) == Some(&plain)
+        {
+            GuessFeedback::Forced
+        } else {
+            GuessFeedback::Consistent
+        }
+    }
+
+    /// Solves a phrase given a set of cipher letters known to stand for vowels.
+    ///
+    /// Only which letters are vowels need be known, not which vowel each one is.
+    pub fn solve_with_vowel_hints<'a>(
+        &self,
+        phrase: &'a Phrase,
+        vowels: &HashSet<u8>,
+    ) -> impl Iterator<Item = String> + 'a {
+        let constraints = Constraints {
+            vowels: Some(vowels),
+            ..Constraints::default()
+        };
+
+        self.guess_phrase(&constraints, HashMap::new(), phrase)
+            .into_iter()
+            .map(move |mapping| phrase.render(&mapping))
+    }
+
+    /// Lists the plaintext letters each cipher letter stands for across all solutions.
+    ///
+    /// Each plaintext letter is paired with the number of solutions that map the cipher letter
+    /// to it, most common first, so the second entry is the next best alternative to offer.
+    pub fn letter_alternatives(&self, phrase: &Phrase) -> HashMap<u8, Vec<(u8, usize)>> {
+        let mut counts: HashMap<u8, HashMap<u8, usize>> = HashMap::new();
+        self.for_each_mapping(phrase, &HashMap::new(), |mapping| {
+            for (cipher, plain) in mapping {
+                *counts.entry(cipher).or_default().entry(plain).or_default() += 1;
+            }
+        });
+
+        counts
+            .into_iter()
+            .map(|(cipher, counts)| {
+                let mut counts: Vec<_> = counts.into_iter().collect();
+                counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+                (cipher, counts)
+            })
+            .collect()
+    }
+
+    /// Counts the distinct plaintext letters each cipher letter takes across all solutions.
+    ///
+    /// A count of one means every solution agrees on the letter. A phrase without solutions
+    /// gives an empty map.
+    pub fn letter_determinacy(&self, phrase: &Phrase) -> HashMap<u8, usize> {
+        let mut plaintext: HashMap<u8, HashSet<u8>> = HashMap::new();
+        for mapping in self.solve_mappings(phrase) {
+            for (cipher, plain) in mapping {
+                plaintext.entry(cipher).or_default().insert(plain);
+            }
+        }
+
+        plaintext
+            .into_iter()
+            .map(|(cipher, plain)| (cipher, plain.len()))
+            .collect()
+    }
+
+    /// Lists the dictionary words the longest cipher word of a phrase may stand for, most
+    /// frequent first, as a place for a human to start.
+    ///
+    /// The first of several equally long words is taken. Words missing from `frequencies` come
+    /// after those with one, and ties are broken alphabetically. Returns the cipher word along
+    /// with its shortlist, or `None` for a phrase without words.
+    pub fn longest_word_shortlist<'p>(
+        &self,
+        phrase: &'p Phrase,
+        frequencies: &HashMap<&str, u64>,
+    ) -> Option<(&'p str, Vec<&'words str>)> {
+        use std::cmp::Reverse;
+
+        let longest = phrase
+            .as_ref()
+            .split_whitespace()
+            .rev()
+            .max_by_key(|word| word.len())?;
+
+        let mut candidates = self.find_candidate_matches(longest, &Mapping::default());
+        candidates.sort_by_key(|&word| (Reverse(frequencies.get(word)), word));
+        Some((longest, candidates))
+    }
+
+    /// Finds the cipher letters that a partial mapping forces to a single plaintext letter.
+    ///
+    /// Each cipher word keeps only the dictionary words that agree with the mapping and with
+    /// some candidate for every other cipher word sharing a letter with it. A letter is forced
+    /// once all the remaining candidates agree on it, and forced letters narrow the candidates
+    /// in turn, so this repeats until nothing more follows. Only newly forced letters are
+    /// returned; a partial mapping that leaves some word without candidates forces nothing.
+    pub fn forced_mappings(&self, phrase: &Phrase, partial: &HashMap<u8, u8>) -> HashMap<u8, u8> {
+        let mut mapping = match Mapping::from_hash_map(partial) {
+            Some(mapping) => mapping,
+            None => return HashMap::new(),
+        };
+        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
+
+        loop {
+            let allowed = match self.allowed_letters(&encrypted_words, &mapping) {
+                Some(allowed) => allowed,
+                None => return HashMap::new(),
+            };
+
+            let forced: HashMap<_, _> = allowed
+                .into_iter()
+                .filter(|(u, plain)| plain.len() == 1 && mapping.get(*u).is_none())
+                .map(|(u, plain)| (u, plain.into_iter().next().unwrap()))
+                .collect();
+
+            if forced.is_empty() {
+                break;
+            }
+            // Words that share no letters can still force two letters to the same plaintext.
+            for (cipher, plain) in forced {
+                if !mapping.try_insert(cipher, plain) {
+                    return HashMap::new();
+                }
+            }
+        }
+
+        mapping
+            .iter()
+            .filter(|(cipher, _)| !partial.contains_key(cipher))
+            .collect()
+    }
+
+    /// Finds the cipher letters that can only stand for vowels, whatever the solution.
+    ///
+    /// Candidate words are narrowed as in `forced_mappings`, and a letter is a vowel if every
+    /// plaintext letter still allowed for it is one. Nothing is deduced for a phrase some word
+    /// of which has no candidates. The result can be passed to `solve_with_vowel_hints`.
+    pub fn deduce_vowels(&self, phrase: &Phrase) -> HashSet<u8> {
+        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
+        self.allowed_letters(&encrypted_words, &Mapping::default())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, plain)| !plain.is_empty() && plain.iter().all(|p| b"aeiou".contains(p)))
+            .map(|(cipher, _)| cipher)
+            .collect()
+    }
+
+    /// Narrows each cipher letter of a phrase to the plaintext letters that every word containing
+    /// it allows under a mapping, returning `None` if some word is left without candidates.
+    ///
+    /// Each cipher word keeps only the dictionary words that agree with the mapping and with the
+    /// letters allowed so far, and this repeats until no more candidates drop out.
+    fn allowed_letters(
+        &self,
+        encrypted_words: &HashSet<&str>,
+        mapping: &Mapping,
+    ) -> Option<HashMap<u8, HashSet<u8>>> {
+        let mut candidates: Vec<Vec<_>> = encrypted_words
+            .iter()
+            .map(|word| {
+                self.find_candidate_matches(word, mapping)
+                    .into_iter()
+                    .filter_map(|candidate| {
+                        key::try_extend_mapping(&Constraints::default(), candidate, word, mapping)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Narrow each letter to the plaintext letters every word containing it allows.
+        loop {
+            let mut allowed: HashMap<u8, HashSet<u8>> = HashMap::new();
+            for (word, extensions) in encrypted_words.iter().zip(&candidates) {
+                for u in word.bytes() {
+                    let here: HashSet<_> = extensions.iter().filter_map(|ext| ext.get(u)).collect();
+                    match allowed.get_mut(&u) {
+                        Some(plain) => plain.retain(|p| here.contains(p)),
+                        None => {
+                            allowed.insert(u, here);
+                        }
+                    }
+                }
+            }
+
+            let before: usize = candidates.iter().map(Vec::len).sum();
+            for extensions in &mut candidates {
+                extensions.retain(|ext| {
+                    ext.iter()
+                        .all(|(u, p)| allowed.get(&u).is_none_or(|plain| plain.contains(&p)))
+                });
+            }
+            let after: usize = candidates.iter().map(Vec::len).sum();
+
+            if candidates.iter().any(Vec::is_empty) {
+                return None;
+            }
+            if after == before {
+                return Some(allowed);
+            }
+        }
+    }
+
+    /// Counts the solutions for a phrase.
+    pub fn solution_count(&self, phrase: &Phrase) -> usize {
+        self.solve_mappings(phrase).len()
+    }
+
+    /// Estimates the number of solutions for a phrase without searching for them.
+    ///
+    /// The estimate is the product of the number of dictionary words matching each distinct
+    /// cipher word, so it is cheap to compute and never less than the true count, but it may be
+    /// far larger.
+    pub fn estimate_solution_count(&self, phrase: &Phrase) -> usize {
+        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
+        encrypted_words
+            .into_iter()
+            .map(|word| self.find_candidate_matches(word, &Mapping::default()).len())
+            .fold(1, usize::saturating_mul)
+    }
+
+    /// Estimates the probability that a phrase has exactly one solution, without searching.
+    ///
+    /// Each cipher word contributes the number of bits needed to pick one of its candidate
+    /// words, while each cipher letter shared between words takes bits away, since the words
+    /// must agree on it. The difference approximates how many wrong solutions to expect, and the
+    /// chance of there being none at all is the estimate.
+    pub fn estimate_uniqueness(&self, phrase: &Phrase) -> f64 {
+        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
+
+        let mut choice = 0.0;
+        let mut words_by_letter: HashMap<u8, usize> = HashMap::new();
+        for word in &encrypted_words {
+            match self.find_candidate_matches(word, &Mapping::default()).len() {
+                0 => return 0.0,
+                candidates => choice += (candidates as f64).log2(),
+            }
+
+            let letters: HashSet<_> = word.bytes().collect();
+            for u in letters {
+                *words_by_letter.entry(u).or_default() += 1;
+            }
+        }
+
+        let agreements: usize = words_by_letter.values().map(|&words| words - 1).sum();
+        let expected_wrong = (choice - agreements as f64 * LETTER_AGREEMENT_BITS).exp2();
+        (-expected_wrong).exp()
+    }
+
+    /// Finds a small set of hints under which the phrase has exactly one solution.
+    ///
+    /// The hints always lead to the same solution: the first in sorted order. Hints are chosen
+    /// greedily, each time picking the letter that rules out the most remaining solutions, so the
+    /// result is small but not guaranteed to be minimal. Returns `None` if the phrase cannot be
+    /// solved at all.
+    pub fn minimal_hints_for_unique(&self, phrase: &Phrase) -> Option<HashMap<u8, u8>> {
+        let solutions = self.solve_mappings(phrase);
+        let target = solutions
+            .iter()
+            .min_by_key(|mapping| phrase.render(mapping))?;
+
+        let mut hints = HashMap::new();
+        let mut remaining: Vec<_> = solutions.iter().collect();
+
+        while remaining.len() > 1 {
+            let (&cipher, &plain) = target
+                .iter()
+                .filter(|(cipher, _)| !hints.contains_key(*cipher))
+                .min_by_key(|&(cipher, plain)| {
+                    let survivors = remaining
+                        .iter()
+                        .filter(|mapping| mapping.get(cipher) == Some(plain))
+                        .count();
+                    (survivors, *cipher)
+                })?;
+
+            hints.insert(cipher, plain);
+            remaining.retain(|mapping| mapping.get(&cipher) == Some(&plain));
+        }
+
+        Some(hints)
+    }
+}
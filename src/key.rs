@@ -0,0 +1,321 @@
+//! Helpers for working with keys, which map cipher letters to plaintext letters.
+
+use crate::{mapping::Mapping, Constraints};
+use hashbrown::{HashMap, HashSet};
+use std::{error::Error, fmt};
+
+/// An error in a partial key, along with the line on which it occurred.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyError {
+    /// The line number, counting from one.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for KeyError {}
+
+/// Reads a partial key, written one `cipher=plain` pair per line.
+///
+/// Letters are case insensitive. Blank lines and lines starting with `#` are ignored. Every
+/// entry must map one letter to one letter, and no letter may appear twice on either side.
+pub fn parse_partial_key(text: &str) -> Result<HashMap<u8, u8>, KeyError> {
+    let mut key = HashMap::new();
+    let mut plaintext = HashSet::new();
+
+    for (idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let error = |message: String| KeyError {
+            line: idx + 1,
+            message,
+        };
+
+        let (cipher, plain) = match line.split_once('=') {
+            Some((cipher, plain)) => (letter(cipher), letter(plain)),
+            None => return Err(error(format!("expected cipher=plain, found {:?}", line))),
+        };
+
+        let (cipher, plain) = match (cipher, plain) {
+            (Some(cipher), Some(plain)) => (cipher, plain),
+            _ => {
+                return Err(error(format!(
+                    "expected a letter on each side of {:?}",
+                    line
+                )))
+            }
+        };
+
+        if key.insert(cipher, plain).is_some() {
+            return Err(error(format!(
+                "{} is mapped more than once",
+                cipher as char
+            )));
+        }
+
+        if !plaintext.insert(plain) {
+            return Err(error(format!(
+                "{} is the target of more than one letter",
+                plain as char
+            )));
+        }
+    }
+
+    Ok(key)
+}
+
+/// A disagreement between two partial keys.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum KeyConflict {
+    /// A cipher letter is mapped to a different plaintext letter by each key.
+    Cipher { cipher: u8, plain: (u8, u8) },
+    /// A plaintext letter is the target of a different cipher letter in each key.
+    Plain { plain: u8, cipher: (u8, u8) },
+}
+
+impl fmt::Display for KeyConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            KeyConflict::Cipher { cipher, plain } => write!(
+                f,
+                "{} is mapped to both {} and {}",
+                cipher as char, plain.0 as char, plain.1 as char
+            ),
+            KeyConflict::Plain { plain, cipher } => write!(
+                f,
+                "{} is the target of both {} and {}",
+                plain as char, cipher.0 as char, cipher.1 as char
+            ),
+        }
+    }
+}
+
+impl Error for KeyConflict {}
+
+/// Combines two partial keys, such as those filled in by two people working on one puzzle.
+///
+/// The keys must agree on every cipher letter they share, and together they must still map no
+/// two cipher letters to the same plaintext letter. The first disagreement found is returned
+/// as the error, with the letter from `a` first.
+pub fn merge_keys(
+    a: &HashMap<u8, u8>,
+    b: &HashMap<u8, u8>,
+) -> Result<HashMap<u8, u8>, KeyConflict> {
+    let sorted = |key: &HashMap<u8, u8>| {
+        let mut pairs: Vec<_> = key
+            .iter()
+            .map(|(&cipher, &plain)| (cipher, plain))
+            .collect();
+        pairs.sort_unstable();
+        pairs
+    };
+
+    let mut key = Mapping::default();
+    for (cipher, plain) in sorted(a).into_iter().chain(sorted(b)) {
+        if key.try_insert(cipher, plain) {
+            continue;
+        }
+
+        return Err(match key.get(cipher) {
+            Some(mapped) => KeyConflict::Cipher {
+                cipher,
+                plain: (mapped, plain),
+            },
+            None => KeyConflict::Plain {
+                plain,
+                cipher: (key.cipher_for(plain).unwrap_or(plain), cipher),
+            },
+        });
+    }
+
+    Ok(key.to_hash_map())
+}
+
+/// Reads a single ascii letter, ignoring surrounding whitespace.
+fn letter(s: &str) -> Option<u8> {
+    match s.trim().as_bytes() {
+        &[u] if u.is_ascii_alphabetic() => Some(u.to_ascii_lowercase()),
+        _ => None,
+    }
+}
+
+/// Recovers the key that turns a ciphertext into a given plaintext.
+///
+/// Words are matched up in order. Returns `None` if the two texts do not line up word for word,
+/// or if no single substitution key could turn one into the other.
+pub fn solution_to_key(cipher: &str, plain: &str) -> Option<HashMap<u8, u8>> {
+    let mut cipher_words = cipher.split_whitespace();
+    let mut plain_words = plain.split_whitespace();
+    let mut key = Mapping::default();
+
+    loop {
+        match (cipher_words.next(), plain_words.next()) {
+            (None, None) => return Some(key.to_hash_map()),
+            (Some(encrypted_word), Some(word)) if encrypted_word.len() == word.len() => {
+                key = try_extend_mapping(&Constraints::default(), word, encrypted_word, &key)?;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Decrypts a ciphertext with a key.
+///
+/// Characters without a mapping are passed through unchanged.
+pub fn key_to_solution(cipher: &str, key: &HashMap<u8, u8>) -> String {
+    cipher
+        .bytes()
+        .map(|u| key.get(&u).copied().unwrap_or(u) as char)
+        .collect()
+}
+
+/// Decrypts a ciphertext with a key that may be incomplete, writing `marker` for each letter
+/// the key does not cover.
+pub fn key_to_solution_marked(cipher: &str, key: &HashMap<u8, u8>, marker: char) -> String {
+    cipher
+        .bytes()
+        .map(|u| match key.get(&u) {
+            Some(&plain) => plain as char,
+            None if u.is_ascii_alphabetic() => marker,
+            None => u as char,
+        })
+        .collect()
+}
+
+/// Counts the cipher letters a key determines.
+pub fn key_coverage(key: &HashMap<u8, u8>) -> usize {
+    key.keys().filter(|u| u.is_ascii_lowercase()).count()
+}
+
+/// Inverts a key, so that it maps plaintext letters back to cipher letters.
+pub fn invert(key: &HashMap<u8, u8>) -> HashMap<u8, u8> {
+    key.iter()
+        .map(|(&cipher, &plain)| (plain, cipher))
+        .collect()
+}
+
+/// Tests whether decrypting a ciphertext and then encrypting the result with the inverse key
+/// reproduces the ciphertext exactly.
+pub fn round_trips(cipher: &str, key: &HashMap<u8, u8>) -> bool {
+    let plain = key_to_solution(cipher, key);
+    key_to_solution(&plain, &invert(key)) == cipher
+}
+
+/// Formats a key on one line as `cipher:plain` pairs in cipher letter order.
+pub fn key_pairs(key: &HashMap<u8, u8>) -> String {
+    let mut pairs: Vec<_> = key.iter().collect();
+    pairs.sort_unstable();
+    pairs
+        .iter()
+        .map(|&(&cipher, &plain)| format!("{}:{}", cipher as char, plain as char))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Formats a key as the 26 plaintext letters for the cipher letters `a` through `z`, with `.`
+/// for cipher letters without a mapping.
+pub fn key_string(key: &HashMap<u8, u8>) -> String {
+    (b'a'..=b'z')
+        .map(|u| key.get(&u).map_or('.', |&u| u as char))
+        .collect()
+}
+
+/// Formats a key as a JSON object from cipher letter to plaintext letter, in cipher letter order.
+pub fn key_json(key: &HashMap<u8, u8>) -> String {
+    let mut pairs: Vec<_> = key.iter().collect();
+    pairs.sort_unstable();
+    let entries: Vec<_> = pairs
+        .iter()
+        .map(|&(&cipher, &plain)| format!("\"{}\":\"{}\"", cipher as char, plain as char))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+/// Formats a key as a grid, with the cipher alphabet above the plaintext alphabet.
+///
+/// Each column holds a cipher letter and the plaintext letter it stands for. Columns for cipher
+/// letters without a mapping are left blank in the second row.
+pub fn key_grid(key: &HashMap<u8, u8>) -> String {
+    grid(&[alphabet_row(), plain_row(key)])
+}
+
+/// Formats a key as a grid as `key_grid` does, adding a third row with the number of times each
+/// cipher letter appears in the ciphertext.
+///
+/// Frequent cipher letters should usually stand for frequent English letters, so the counts
+/// make a key easy to sanity check.
+pub fn key_grid_with_counts(cipher: &str, key: &HashMap<u8, u8>) -> String {
+    let counts = letter_counts(cipher)
+        .iter()
+        .map(|&count| {
+            if count == 0 {
+                String::new()
+            } else {
+                count.to_string()
+            }
+        })
+        .collect();
+
+    grid(&[alphabet_row(), plain_row(key), counts])
+}
+
+/// Counts how often each lowercase letter appears in some text, indexed from `a`.
+pub fn letter_counts(text: &str) -> [usize; 26] {
+    let mut counts = [0; 26];
+    for u in text.bytes().filter(u8::is_ascii_lowercase) {
+        counts[(u - b'a') as usize] += 1;
+    }
+    counts
+}
+
+fn alphabet_row() -> Vec<String> {
+    (b'a'..=b'z').map(|u| (u as char).to_string()).collect()
+}
+
+fn plain_row(key: &HashMap<u8, u8>) -> Vec<String> {
+    (b'a'..=b'z')
+        .map(|u| key.get(&u).map_or(' ', |&u| u as char).to_string())
+        .collect()
+}
+
+/// Lays out rows of cells in columns as wide as their widest cell, separated by single spaces.
+fn grid(rows: &[Vec<String>]) -> String {
+    let width = rows.iter().flatten().map(String::len).max().unwrap_or(0);
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| format!("{:>width$}", cell, width = width))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Attempts to extend mapping based on an encrypted word and a candidate solution.
+pub(crate) fn try_extend_mapping(
+    constraints: &Constraints,
+    word: &str,
+    encrypted_word: &str,
+    mapping: &Mapping,
+) -> Option<Mapping> {
+    let mut new_mapping = *mapping;
+
+    for (u_encoded, u_decoded) in encrypted_word.bytes().zip(word.bytes()) {
+        if !constraints.admits(u_encoded, u_decoded)
+            || !new_mapping.try_insert(u_encoded, u_decoded)
+        {
+            return None;
+        }
+    }
+
+    Some(new_mapping)
+}
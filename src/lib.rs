@@ -0,0 +1,870 @@
+// Reference: https://github.com/davidkellis/cryptogram/blob/master/src/cryptogram.cr
+// David's cryptogram solver.
+
+pub mod adfgvx;
+pub mod affine;
+pub mod analysis;
+pub mod combo;
+pub mod cryptarithm;
+pub mod key;
+pub mod profile;
+pub mod railfence;
+pub mod rank;
+
+mod best_first;
+mod cache;
+mod channel;
+mod hints;
+mod mapping;
+mod search_tree;
+
+pub use best_first::RankedSolutions;
+pub use hints::GuessFeedback;
+
+use hashbrown::{HashMap, HashSet};
+use mapping::Mapping;
+use rayon::prelude::*;
+use std::{
+    cell::{Cell, RefCell},
+    error::Error,
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// The enable1 word list, one word per line, which the binary uses when no dictionary is given.
+#[cfg(feature = "embedded-dict")]
+pub const DEFAULT_DICTIONARY: &str = include_str!("../resources/enable1.txt");
+
+/// Represents a phrase to be solved.
+///
+/// A phrase differs from an ordinary string in that a phrase is guaranteed to be lowercase
+/// ascii letters separated by whitespace.
+#[derive(Debug)]
+pub struct Phrase(String);
+
+/// The reason a string could not be read as a phrase.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PhraseError {
+    /// The string contains characters outside the ascii range.
+    NotAscii,
+    /// The string contains a character that is neither a letter nor whitespace.
+    NonLetter(char),
+    /// A plaintext segment is opened without being closed, or closed without being opened.
+    UnbalancedBracket,
+}
+
+impl fmt::Display for PhraseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PhraseError::NotAscii => f.write_str("phrase is not ascii"),
+            PhraseError::NonLetter(c) => write!(f, "phrase contains non-letter character {:?}", c),
+            PhraseError::UnbalancedBracket => f.write_str("phrase has an unbalanced bracket"),
+        }
+    }
+}
+
+impl Error for PhraseError {}
+
+impl Phrase {
+    /// Reads a phrase in strict substitution mode.
+    ///
+    /// Every character must be a letter or whitespace. Punctuation would otherwise be treated as
+    /// part of a word and silently produce nonsense patterns, so it is rejected instead.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: impl AsRef<str>) -> Result<Phrase, PhraseError> {
+        let s = s.as_ref();
+        if !s.is_ascii() {
+            return Err(PhraseError::NotAscii);
+        }
+
+        match s
+            .chars()
+            .find(|c| !c.is_ascii_alphabetic() && !c.is_ascii_whitespace())
+        {
+            Some(c) => Err(PhraseError::NonLetter(c)),
+            None => Ok(Phrase(s.to_ascii_lowercase())),
+        }
+    }
+
+    /// Reads a phrase after removing any characters other than letters and whitespace.
+    ///
+    /// Apostrophes are dropped, joining contractions into one word, while other punctuation
+    /// separates words. Text of several sentences thus becomes one phrase, to be solved under a
+    /// single key, even where a sentence ends without a space after it.
+    pub fn from_str_stripped(s: impl AsRef<str>) -> Result<Phrase, PhraseError> {
+        let s = s.as_ref();
+        if !s.is_ascii() {
+            return Err(PhraseError::NotAscii);
+        }
+
+        let text: String = s
+            .chars()
+            .filter(|&c| c != '\'')
+            .map(|c| if c.is_ascii_alphabetic() { c } else { ' ' })
+            .collect();
+        Phrase::from_str(text.trim())
+    }
+
+    /// Reads a phrase in which some segments are already plaintext, marked with square brackets.
+    ///
+    /// Bracketed letters were never enciphered, so each stands for itself. The phrase returned
+    /// includes them without the brackets, and the mapping fixes every letter they contain to
+    /// itself, ready to be used as hints.
+    pub fn from_str_with_plaintext(
+        s: impl AsRef<str>,
+    ) -> Result<(Phrase, HashMap<u8, u8>), PhraseError> {
+        let mut text = String::new();
+        let mut fixed = HashMap::new();
+        let mut in_plaintext = false;
+
+        for c in s.as_ref().chars() {
+            match c {
+                '[' if !in_plaintext => in_plaintext = true,
+                ']' if in_plaintext => in_plaintext = false,
+                '[' | ']' => return Err(PhraseError::UnbalancedBracket),
+                c => {
+                    if in_plaintext && c.is_ascii_alphabetic() {
+                        let u = c.to_ascii_lowercase() as u8;
+                        fixed.insert(u, u);
+                    }
+                    text.push(c);
+                }
+            }
+        }
+
+        if in_plaintext {
+            return Err(PhraseError::UnbalancedBracket);
+        }
+
+        Ok((Phrase::from_str(text)?, fixed))
+    }
+
+    /// Counts the distinct letters in the phrase, which is also the most a key for it can map.
+    pub fn distinct_letter_count(&self) -> usize {
+        let mut seen = [false; 256];
+        self.0
+            .bytes()
+            .filter(|&u| u.is_ascii_alphabetic() && !std::mem::replace(&mut seen[u as usize], true))
+            .count()
+    }
+
+    /// Decrypts the phrase with a letter mapping.
+    ///
+    /// Characters without a mapping are passed through unchanged.
+    pub fn render(&self, mapping: &HashMap<u8, u8>) -> String {
+        key::key_to_solution(&self.0, mapping)
+    }
+
+    /// Decrypts the phrase with a letter mapping that may be incomplete.
+    ///
+    /// Letters without a mapping are written as `marker`, so they cannot be mistaken for
+    /// recovered plaintext. Whitespace is passed through unchanged.
+    pub fn render_marked(&self, mapping: &HashMap<u8, u8>, marker: char) -> String {
+        key::key_to_solution_marked(&self.0, mapping, marker)
+    }
+}
+
+impl AsRef<str> for Phrase {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Hash)]
+struct Pattern(Vec<u8>);
+
+impl Pattern {
+    fn from_str(s: &str) -> Self {
+        // Symbols are stored offset by one so that zero can stand for "not yet seen." A fixed
+        // table is much cheaper than hashing when building patterns for the whole dictionary.
+        let mut next_symbol = 1;
+        let mut symbols = Vec::with_capacity(s.len());
+        let mut symbol_map = [0u8; 256];
+
+        for u in s.bytes() {
+            let symbol = &mut symbol_map[u as usize];
+            if *symbol == 0 {
+                *symbol = next_symbol;
+                next_symbol += 1;
+            }
+            symbols.push(*symbol - 1);
+        }
+
+        Pattern(symbols)
+    }
+}
+
+/// A test applied to the candidate plaintext words for one word of a phrase.
+pub type WordPredicate<'a> = &'a dyn Fn(&str) -> bool;
+
+/// A test of whether a cipher word, given first, may stand for a candidate word.
+type WordFilter<'a> = &'a dyn Fn(&str, &str) -> bool;
+
+/// Restrictions on the search beyond those imposed by the dictionary.
+#[derive(Default)]
+struct Constraints<'a> {
+    /// Cipher letters known to stand for vowels.
+    vowels: Option<&'a HashSet<u8>>,
+    /// Attempts to extend a mapping that remain before the search gives up.
+    budget: Option<&'a Cell<usize>>,
+    /// Set to stop the search; branches not yet entered are abandoned.
+    cancelled: Option<&'a Cell<bool>>,
+    /// Receives the time taken to find candidates for every word before the first guess.
+    first_pass: Option<&'a Cell<Option<Duration>>>,
+    /// Receives the number of branches taken at each depth of the search, across the whole
+    /// search.
+    branching: Option<&'a RefCell<Vec<usize>>>,
+    /// Known plaintext endings for some cipher words.
+    suffixes: Option<&'a HashMap<&'a str, String>>,
+    /// Decides whether a cipher word may stand for a candidate word.
+    word_filter: Option<WordFilter<'a>>,
+    /// Receives every candidate word tried, in the shape of the search.
+    search_tree: Option<&'a RefCell<search_tree::SearchTree>>,
+}
+
+impl Constraints<'_> {
+    /// Takes one attempt from the budget, returning false if none are left.
+    fn spend(&self) -> bool {
+        match self.budget {
+            Some(budget) if budget.get() == 0 => false,
+            Some(budget) => {
+                budget.set(budget.get() - 1);
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Tests whether the search has been stopped.
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.is_some_and(Cell::get)
+    }
+
+    /// Finds the plaintext ending a cipher word is known to have, which may be empty.
+    fn suffix_for(&self, encrypted_word: &str) -> &str {
+        self.suffixes
+            .and_then(|suffixes| suffixes.get(encrypted_word))
+            .map_or("", String::as_str)
+    }
+
+    /// Tests whether a cipher word may decrypt to a given candidate word.
+    fn allows(&self, encrypted_word: &str, candidate: &str) -> bool {
+        self.word_filter
+            .is_none_or(|filter| filter(encrypted_word, candidate))
+    }
+
+    /// Tests whether a cipher letter may decrypt to a given plaintext letter.
+    fn admits(&self, u_encoded: u8, u_decoded: u8) -> bool {
+        match self.vowels {
+            Some(vowels) if vowels.contains(&u_encoded) => b"aeiou".contains(&u_decoded),
+            _ => true,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Solver<'words> {
+    words_by_pattern: HashMap<Pattern, HashSet<&'words str>>,
+    words_by_character_and_index: HashMap<usize, HashMap<u8, HashSet<&'words str>>>,
+    cache: Option<cache::SolutionCache>,
+}
+
+#[cfg(feature = "embedded-dict")]
+impl Solver<'static> {
+    /// Builds a solver from `DEFAULT_DICTIONARY`.
+    pub fn default_english() -> Self {
+        let words: Vec<_> = DEFAULT_DICTIONARY.split_whitespace().collect();
+        Solver::from_dictionary(&words)
+    }
+}
+
+impl<'words> Solver<'words> {
+    pub fn from_dictionary(words: &[&'words str]) -> Self {
+        Solver::from_dictionary_profiled(words).0
+    }
+
+    /// Builds a solver from only the words that satisfy a predicate, such as membership in a
+    /// category of words.
+    pub fn from_dictionary_filtered(
+        words: &[&'words str],
+        predicate: impl Fn(&str) -> bool,
+    ) -> Self {
+        let words: Vec<_> = words
+            .iter()
+            .copied()
+            .filter(|word| predicate(word))
+            .collect();
+        Solver::from_dictionary(&words)
+    }
+
+    /// Builds a solver as `from_dictionary` does, timing each index as it is built.
+    pub fn from_dictionary_profiled(words: &[&'words str]) -> (Self, profile::BuildProfile) {
+        let mut solver = Solver::default();
+
+        let start = Instant::now();
+        for &word in words {
+            solver
+                .words_by_pattern
+                .entry(Pattern::from_str(word))
+                .or_default()
+                .insert(word);
+        }
+        let pattern_index = start.elapsed();
+
+        let start = Instant::now();
+        for &word in words {
+            for (idx, u) in word.bytes().enumerate() {
+                solver
+                    .words_by_character_and_index
+                    .entry(idx)
+                    .or_default()
+                    .entry(u)
+                    .or_default()
+                    .insert(word);
+            }
+        }
+        let position_index = start.elapsed();
+
+        let profile = profile::BuildProfile {
+            pattern_index,
+            position_index,
+        };
+        (solver, profile)
+    }
+
+    fn words_by_pattern(&self, word: &str) -> Option<&HashSet<&'words str>> {
+        self.words_by_pattern.get(&Pattern::from_str(word))
+    }
+
+    fn contains_word(&self, word: &str) -> bool {
+        self.words_by_pattern(word)
+            .is_some_and(|words| words.contains(word))
+    }
+
+    fn words_by_character_and_index(&self, u: u8, idx: usize) -> Option<&HashSet<&'words str>> {
+        self.words_by_character_and_index
+            .get(&idx)
+            .and_then(|by_char| by_char.get(&u))
+    }
+
+    /// Finds the dictionary words matching a plaintext pattern, in sorted order.
+    ///
+    /// Each `.` in the pattern matches any letter and every other character must match exactly,
+    /// so `c.t` matches cat, cot and cut. Only words as long as the pattern match.
+    pub fn words_matching(&self, pattern: &str) -> Vec<&'words str> {
+        let pattern = pattern.to_ascii_lowercase();
+        let fixed: Vec<_> = pattern
+            .bytes()
+            .enumerate()
+            .filter(|&(_, u)| u != b'.')
+            .collect();
+
+        let mut words: Vec<_> = match fixed
+            .iter()
+            .map(|&(idx, u)| self.words_by_character_and_index(u, idx))
+            .min_by_key(|words| words.map_or(0, HashSet::len))
+        {
+            Some(None) => return Vec::new(),
+            Some(Some(words)) => words.iter().copied().collect(),
+            // With no letters to go on, every word of the right length matches.
+            None => self
+                .words_by_pattern
+                .iter()
+                .filter(|(class, _)| class.0.len() == pattern.len())
+                .flat_map(|(_, words)| words.iter().copied())
+                .collect(),
+        };
+
+        words.retain(|word| {
+            let bytes = word.as_bytes();
+            bytes.len() == pattern.len() && fixed.iter().all(|&(idx, u)| bytes[idx] == u)
+        });
+        words.sort_unstable();
+        words
+    }
+
+    // FIXME: use internal iteration to print solutions as they are discovered.
+    pub fn solve<'a>(&self, phrase: &'a Phrase) -> impl Iterator<Item = String> + 'a {
+        self.solve_mappings(phrase)
+            .into_iter()
+            .map(move |mapping| phrase.render(&mapping))
+    }
+
+    /// Finds every letter mapping that decrypts the phrase into dictionary words.
+    pub fn solve_mappings(&self, phrase: &Phrase) -> Vec<HashMap<u8, u8>> {
+        let solve = || self.guess_phrase(&Constraints::default(), HashMap::new(), phrase);
+        match &self.cache {
+            Some(cache) => cache.get_or_solve(phrase, solve),
+            None => solve(),
+        }
+    }
+
+    /// Finds every letter mapping that decrypts each of the given encrypted words.
+    ///
+    /// This skips building a phrase, for callers that have already split their input into words
+    /// by whatever means suit it. Repeated words are solved only once.
+    pub fn solve_words(&self, encrypted_words: &[&str]) -> Vec<HashMap<u8, u8>> {
+        let encrypted_words: HashSet<_> = encrypted_words.iter().copied().collect();
+        let encrypted_words: Vec<_> = encrypted_words.into_iter().collect();
+
+        let mut mappings = Vec::new();
+        self.guess(
+            &Constraints::default(),
+            Mapping::default(),
+            &encrypted_words,
+            &mut |mapping| mappings.push(mapping.to_hash_map()),
+        );
+        mappings
+    }
+
+    /// Passes each letter mapping that decrypts the phrase to `visit` as soon as it is found.
+    ///
+    /// Unlike `solve_mappings`, solutions are never collected, so memory use does not grow with
+    /// the number of solutions. Known letter mappings may be given as hints; hints that would
+    /// map two cipher letters to the same plaintext letter admit no solutions.
+    pub fn for_each_mapping(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+        mut visit: impl FnMut(HashMap<u8, u8>),
+    ) {
+        let plaintext: HashSet<_> = hints.values().collect();
+        if plaintext.len() != hints.len() {
+            return;
+        }
+
+        self.visit_phrases(
+            &Constraints::default(),
+            hints.clone(),
+            &[phrase],
+            &mut visit,
+        );
+    }
+
+    /// Solves a phrase, keeping at most `cap` solutions for each distinct first word.
+    ///
+    /// When many solutions differ only near the end, this gives a more varied sample than
+    /// simply taking the first few. Hints work as they do for `for_each_mapping`.
+    pub fn solve_with_first_word_cap(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+        cap: usize,
+    ) -> Vec<String> {
+        let first_word = phrase
+            .as_ref()
+            .split_whitespace()
+            .next()
+            .unwrap_or_default();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        let mut solutions = Vec::new();
+
+        self.for_each_mapping(phrase, hints, |mapping| {
+            let count = counts
+                .entry(key::key_to_solution(first_word, &mapping))
+                .or_default();
+            if *count < cap {
+                *count += 1;
+                solutions.push(phrase.render(&mapping));
+            }
+        });
+
+        solutions
+    }
+
+    /// Solves several phrases under a single key.
+    ///
+    /// Each solution holds the decryption of every phrase, in the order given. Solving jointly
+    /// is stricter than solving each phrase separately, because every word constrains the key.
+    pub fn solve_jointly(&self, phrases: &[&Phrase]) -> Vec<Vec<String>> {
+        self.guess_phrases(&Constraints::default(), HashMap::new(), phrases)
+            .into_iter()
+            .map(|mapping| {
+                phrases
+                    .iter()
+                    .map(|phrase| phrase.render(&mapping))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Solves each of several independent phrases, spreading the phrases across threads.
+    ///
+    /// The solver is only read while solving, so one solver serves every thread. The solutions
+    /// for each phrase are returned in the order the phrases were given, exactly as `solve`
+    /// would produce them.
+    pub fn solve_batch_parallel(&self, phrases: &[Phrase]) -> Vec<Vec<String>> {
+        phrases
+            .par_iter()
+            .map(|phrase| self.solve(phrase).collect())
+            .collect()
+    }
+
+    /// Solves a phrase given the plaintext endings of some of its words, such as `ing`.
+    ///
+    /// Each ending is given with the index of its word in the phrase, counting from zero.
+    /// Endings for words past the end of the phrase are ignored, and endings for the same cipher
+    /// word that disagree admit no solutions.
+    pub fn solve_with_suffixes(&self, phrase: &Phrase, suffixes: &[(usize, &str)]) -> Vec<String> {
+        let words: Vec<_> = phrase.as_ref().split_whitespace().collect();
+        let mut by_word: HashMap<&str, String> = HashMap::new();
+
+        for &(idx, suffix) in suffixes {
+            let word = match words.get(idx) {
+                Some(word) => *word,
+                None => continue,
+            };
+            let suffix = suffix.to_ascii_lowercase();
+            let known = by_word.entry(word).or_default();
+
+            // Keep the longer of two endings, as long as one ends the other.
+            if suffix.ends_with(known.as_str()) {
+                *known = suffix;
+            } else if !known.ends_with(&suffix) {
+                return Vec::new();
+            }
+        }
+
+        let constraints = Constraints {
+            suffixes: Some(&by_word),
+            ..Constraints::default()
+        };
+
+        self.guess_phrase(&constraints, HashMap::new(), phrase)
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect()
+    }
+
+    /// Solves a phrase given predicates that some of its words must satisfy.
+    ///
+    /// Each predicate is given with the index of its word in the phrase, counting from zero, and
+    /// is applied to the candidate plaintext words for it. This captures partial knowledge of a
+    /// word's structure, such as a doubled letter in a known place. Predicates for words past
+    /// the end of the phrase are ignored.
+    pub fn solve_with_word_predicates(
+        &self,
+        phrase: &Phrase,
+        predicates: &[(usize, WordPredicate)],
+    ) -> Vec<String> {
+        let words: Vec<_> = phrase.as_ref().split_whitespace().collect();
+        let mut by_word: HashMap<&str, Vec<WordPredicate>> = HashMap::new();
+        for &(idx, predicate) in predicates {
+            if let Some(word) = words.get(idx) {
+                by_word.entry(word).or_default().push(predicate);
+            }
+        }
+
+        let filter = |encrypted_word: &str, candidate: &str| {
+            by_word
+                .get(encrypted_word)
+                .is_none_or(|predicates| predicates.iter().all(|predicate| predicate(candidate)))
+        };
+        let constraints = Constraints {
+            word_filter: Some(&filter),
+            ..Constraints::default()
+        };
+
+        self.guess_phrase(&constraints, HashMap::new(), phrase)
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect()
+    }
+
+    /// Solves mixed-case text in which capitalized words are proper nouns.
+    ///
+    /// Capitalized cipher words match only words in `proper_nouns`, and the rest only words in
+    /// `common_words`. Both lists must be part of the solver's dictionary for their words to be
+    /// found at all. Text in a single case says nothing about which words are names, so every
+    /// word of it is taken to be a common word. A cipher word written both ways must match a
+    /// word in both lists. Hints work as they do for `solve_with_hints`.
+    pub fn solve_with_proper_nouns(
+        &self,
+        text: &str,
+        hints: &HashMap<u8, u8>,
+        proper_nouns: &HashSet<&str>,
+        common_words: &HashSet<&str>,
+    ) -> Result<Vec<String>, PhraseError> {
+        let phrase = Phrase::from_str(text)?;
+        let mixed_case = text.bytes().any(|u| u.is_ascii_uppercase())
+            && text.bytes().any(|u| u.is_ascii_lowercase());
+
+        let mut capitalized = HashSet::new();
+        let mut lowercase = HashSet::new();
+        for (token, word) in text
+            .split_whitespace()
+            .zip(phrase.as_ref().split_whitespace())
+        {
+            if mixed_case && token.starts_with(|c: char| c.is_ascii_uppercase()) {
+                capitalized.insert(word);
+            } else {
+                lowercase.insert(word);
+            }
+        }
+
+        let filter = |encrypted_word: &str, candidate: &str| {
+            (!capitalized.contains(encrypted_word) || proper_nouns.contains(candidate))
+                && (!lowercase.contains(encrypted_word) || common_words.contains(candidate))
+        };
+        let constraints = Constraints {
+            word_filter: Some(&filter),
+            ..Constraints::default()
+        };
+
+        Ok(self
+            .guess_phrase(&constraints, hints.clone(), &phrase)
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect())
+    }
+
+    /// Solves a phrase with a fixed amount of work.
+    ///
+    /// The budget caps the number of attempts to extend a mapping with a candidate word. Once it
+    /// runs out, the solutions found so far are returned. Unlike a timeout, the same budget
+    /// always yields the same solutions, whatever the machine.
+    pub fn solve_with_budget<'a>(
+        &self,
+        phrase: &'a Phrase,
+        budget: usize,
+    ) -> impl Iterator<Item = String> + 'a {
+        let budget = Cell::new(budget);
+        let constraints = Constraints {
+            budget: Some(&budget),
+            ..Constraints::default()
+        };
+
+        self.guess_phrase(&constraints, HashMap::new(), phrase)
+            .into_iter()
+            .map(move |mapping| phrase.render(&mapping))
+    }
+
+    /// Finds a single solution, abandoning the search as soon as it is found.
+    ///
+    /// This is the quickest way to learn whether a phrase can be solved at all. The solution
+    /// found is the first in the search order, which is reproducible but says nothing about how
+    /// likely it is. Returns `None` if the phrase has no solution. Hints work as they do for
+    /// `for_each_mapping`.
+    pub fn solve_first(&self, phrase: &Phrase, hints: &HashMap<u8, u8>) -> Option<String> {
+        let plaintext: HashSet<_> = hints.values().collect();
+        if plaintext.len() != hints.len() {
+            return None;
+        }
+
+        let cancelled = Cell::new(false);
+        let constraints = Constraints {
+            cancelled: Some(&cancelled),
+            ..Constraints::default()
+        };
+
+        let mut first = None;
+        self.visit_phrases(&constraints, hints.clone(), &[phrase], &mut |mapping| {
+            first = Some(phrase.render(&mapping));
+            cancelled.set(true);
+        });
+        first
+    }
+
+    fn guess_phrase(
+        &self,
+        constraints: &Constraints,
+        mapping: HashMap<u8, u8>,
+        phrase: &Phrase,
+    ) -> Vec<HashMap<u8, u8>> {
+        self.guess_phrases(constraints, mapping, &[phrase])
+    }
+
+    fn guess_phrases(
+        &self,
+        constraints: &Constraints,
+        mapping: HashMap<u8, u8>,
+        phrases: &[&Phrase],
+    ) -> Vec<HashMap<u8, u8>> {
+        let mut mappings = Vec::new();
+        self.visit_phrases(constraints, mapping, phrases, &mut |mapping| {
+            mappings.push(mapping)
+        });
+        mappings
+    }
+
+    fn visit_phrases(
+        &self,
+        constraints: &Constraints,
+        mapping: HashMap<u8, u8>,
+        phrases: &[&Phrase],
+        visit: &mut dyn FnMut(HashMap<u8, u8>),
+    ) {
+        // Phrases contain only letters and whitespace, so splitting on whitespace yields words.
+        let encrypted_words: HashSet<_> = phrases
+            .iter()
+            .flat_map(|phrase| phrase.as_ref().split_whitespace())
+            .collect();
+        let encrypted_words: Vec<_> = encrypted_words.into_iter().collect();
+
+        // Hints mapping two cipher letters to one plaintext letter admit no solutions.
+        if let Some(mapping) = Mapping::from_hash_map(&mapping) {
+            self.guess(constraints, mapping, &encrypted_words, &mut |mapping| {
+                visit(mapping.to_hash_map())
+            })
+        }
+    }
+
+    /// Searches for mappings that decrypt every encrypted word, passing each one to `visit` as it
+    /// is found.
+    fn guess(
+        &self,
+        constraints: &Constraints,
+        mapping: Mapping,
+        encrypted_words: &[&str],
+        visit: &mut dyn FnMut(Mapping),
+    ) {
+        use std::cmp::Reverse;
+
+        if constraints.is_cancelled() {
+            return;
+        }
+
+        let start = Instant::now();
+        let mut encrypted_words: Vec<_> = encrypted_words
+            .iter()
+            .map(|word| {
+                let suffix = constraints.suffix_for(word);
+                let mut candidate_matches =
+                    self.find_candidate_matches_ending(word, &mapping, suffix);
+                candidate_matches.retain(|candidate| constraints.allows(word, candidate));
+                (word, candidate_matches)
+            })
+            .collect();
+
+        // Only the outermost call, which happens first, records its time.
+        if let Some(first_pass) = constraints.first_pass {
+            if first_pass.get().is_none() {
+                first_pass.set(Some(start.elapsed()));
+            }
+        }
+
+        // Ties are broken by the words themselves so that the search always runs in the same
+        // order, which a budget needs to be reproducible.
+        encrypted_words.sort_by_key(|pair| (Reverse(pair.1.len()), Reverse(*pair.0)));
+
+        match encrypted_words.pop() {
+            None => {
+                if let Some(tree) = constraints.search_tree {
+                    tree.borrow_mut().solved();
+                }
+                visit(mapping)
+            }
+            Some((encrypted_word, mut candidate_words)) => {
+                candidate_words.sort_unstable();
+
+                let mut candidate_mappings = Vec::new();
+                let mut candidate_nodes = Vec::new();
+
+                for word in candidate_words {
+                    if !constraints.spend() {
+                        break;
+                    }
+
+                    let extended =
+                        key::try_extend_mapping(constraints, word, encrypted_word, &mapping);
+
+                    if let Some(tree) = constraints.search_tree {
+                        let node = tree
+                            .borrow_mut()
+                            .add(encrypted_word, word, extended.is_some());
+                        if extended.is_some() {
+                            candidate_nodes.push(node);
+                        }
+                    }
+
+                    if let Some(mapping) = extended {
+                        candidate_mappings.push(mapping);
+                    }
+                }
+
+                if let Some(branching) = constraints.branching {
+                    // The outermost call sees every word, so it sets the number of levels.
+                    let mut branching = branching.borrow_mut();
+                    if branching.is_empty() {
+                        branching.resize(encrypted_words.len() + 1, 0);
+                    }
+                    let depth = branching.len() - encrypted_words.len() - 1;
+                    branching[depth] += candidate_mappings.len();
+                }
+
+                let encrypted_words: Vec<_> =
+                    encrypted_words.iter().map(|&(&word, _)| word).collect();
+
+                for (idx, mapping) in candidate_mappings.into_iter().enumerate() {
+                    // Each branch is searched beneath its own node, then the parent is restored.
+                    let parent = constraints
+                        .search_tree
+                        .map(|tree| tree.borrow_mut().enter(candidate_nodes[idx]));
+
+                    self.guess(constraints, mapping, &encrypted_words, visit);
+
+                    if let (Some(tree), Some(parent)) = (constraints.search_tree, parent) {
+                        tree.borrow_mut().enter(parent);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the dictionary words an encrypted word may stand for under a partial mapping.
+    fn find_candidate_matches(&self, word: &str, mapping: &Mapping) -> Vec<&'words str> {
+        self.find_candidate_matches_ending(word, mapping, "")
+    }
+
+    /// Finds candidates as `find_candidate_matches` does, keeping only words with a known
+    /// plaintext ending.
+    ///
+    /// Short words fall into pattern classes of hundreds or thousands of words, so rather than
+    /// copying the whole class and whittling it down, this walks whichever of the pattern class
+    /// and the positional sets for already mapped letters is smallest. Letters of the ending are
+    /// pinned to positions counted back from the end of the word and treated the same way.
+    /// Pinned letters are then checked by comparing bytes, which is far cheaper than probing the
+    /// other sets.
+    fn find_candidate_matches_ending(
+        &self,
+        word: &str,
+        mapping: &Mapping,
+        suffix: &str,
+    ) -> Vec<&'words str> {
+        let pattern_class = match self.words_by_pattern(word) {
+            Some(words) if suffix.len() <= word.len() => words,
+            _ => return Vec::new(),
+        };
+
+        let offset = word.len() - suffix.len();
+        let pinned = word
+            .bytes()
+            .enumerate()
+            .filter_map(|(idx, u)| mapping.get(u).map(|mapped_char| (idx, mapped_char)))
+            .chain(suffix.bytes().enumerate().map(|(idx, u)| (offset + idx, u)));
+
+        let mut smallest = pattern_class;
+        let mut fixed = Vec::new();
+        for (idx, u) in pinned {
+            match self.words_by_character_and_index(u, idx) {
+                Some(words) if words.len() < smallest.len() => smallest = words,
+                Some(_) => (),
+                None => return Vec::new(),
+            }
+            fixed.push((idx, u));
+        }
+
+        // Positional sets hold words of every length, so their words must still be checked
+        // against the pattern.
+        let check_pattern = !std::ptr::eq(smallest, pattern_class);
+        smallest
+            .iter()
+            .copied()
+            .filter(|candidate| {
+                let bytes = candidate.as_bytes();
+                fixed.iter().all(|&(idx, u)| bytes.get(idx) == Some(&u))
+                    && (!check_pattern || pattern_class.contains(candidate))
+            })
+            .collect()
+    }
+}
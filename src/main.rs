@@ -1,7 +1,18 @@
-// Reference: https://github.com/davidkellis/cryptogram/blob/master/src/cryptogram.cr
-// David's cryptogram solver.
-
+mod csv;
+mod decode;
+mod dictionary;
+mod options;
+mod play;
+mod serve;
+mod template;
+
+use cryptid::{analysis, key, rank, Phrase, Solver};
 use hashbrown::{HashMap, HashSet};
+use options::{Command, Emit, Format, GroupBy, Options};
+use std::{borrow::Cow, time::Duration};
+
+/// Stands in for letters a key does not cover.
+const UNKNOWN_MARKER: char = '·';
 
 macro_rules! time {
     ($e:expr) => {{
@@ -12,231 +23,813 @@ macro_rules! time {
     }};
 }
 
-/// Represents a phrase to be solved.
+/// Groups solutions by their first word, with the largest groups first.
+fn group_by_first_word(solutions: &[String]) -> Vec<(&str, Vec<&str>)> {
+    let mut groups: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for solution in solutions {
+        let first_word = solution.split_whitespace().next().unwrap_or_default();
+        groups.entry(first_word).or_default().push(solution);
+    }
+
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.len().cmp(&a.1.len()).then_with(|| a.0.cmp(b.0)));
+    groups
+}
+
+/// The words of the dictionary built into the binary, used when no other is given.
+#[cfg(feature = "embedded-dict")]
+fn embedded_words() -> Vec<&'static str> {
+    // Enable1.txt does not include words like A or I. It may be preferable to employ a custom
+    // word list or, alternatively, /usr/share/dict/words
+    cryptid::DEFAULT_DICTIONARY.split_whitespace().collect()
+}
+
+/// Stands in for the built-in dictionary in builds without one, where a dictionary must be
+/// given on the command line.
+#[cfg(not(feature = "embedded-dict"))]
+fn embedded_words() -> Vec<&'static str> {
+    eprintln!("No dictionary given; use --dict or --dict-dir");
+    eprintln!("This build has no built-in dictionary (the embedded-dict feature is off).");
+    std::process::exit(1);
+}
+
+/// Loads any dictionaries named on the command line.
 ///
-/// A phrase differs from an ordinary string in that a phrase is guaranteed to be lowercase
-/// ascii text.
-#[derive(Debug)]
-struct Phrase(String);
-
-impl Phrase {
-    fn from_str(s: impl AsRef<str>) -> Option<Phrase> {
-        let s = s.as_ref();
-        if s.is_ascii() {
-            Some(Phrase(s.to_ascii_lowercase()))
+/// Individual word lists that cannot be read are reported and skipped; it is only an error if
+/// dictionaries were requested and none of them could be loaded.
+fn load_dictionaries(options: &Options) -> Vec<String> {
+    use std::process;
+
+    let mut lists = Vec::new();
+    let mut requested = false;
+
+    for path in &options.dictionaries {
+        requested = true;
+        match dictionary::read(path) {
+            Ok(list) => lists.push(list),
+            Err(e) => eprintln!("Unable to read {}: {}", path.display(), e),
+        }
+    }
+
+    if let Some(dir) = &options.dictionary_dir {
+        requested = true;
+        match dictionary::read_dir(dir) {
+            Ok(entries) => {
+                for (path, list) in entries {
+                    match list {
+                        Ok(list) => lists.push(list),
+                        Err(e) => eprintln!("Unable to read {}: {}", path.display(), e),
+                    }
+                }
+            }
+            Err(e) => eprintln!("Unable to read {}: {}", dir.display(), e),
+        }
+    }
+
+    if requested && lists.is_empty() {
+        eprintln!("No dictionaries could be loaded");
+        process::exit(1);
+    }
+
+    lists
+}
+
+fn main() {
+    use std::{env, fs, io, net::TcpListener, process};
+
+    let options = Options::from_args(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let lists = load_dictionaries(&options);
+    let theme_list = options.theme.as_ref().map(|path| {
+        dictionary::read(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read {}: {}", path.display(), e);
+            process::exit(1);
+        })
+    });
+    let theme: HashSet<_> = theme_list
+        .iter()
+        .flat_map(|list| list.split_whitespace())
+        .collect();
+
+    let hints = options
+        .key_file
+        .as_ref()
+        .map(|path| {
+            let text = fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Unable to read {}: {}", path.display(), e);
+                process::exit(1);
+            });
+            key::parse_partial_key(&text).unwrap_or_else(|e| {
+                eprintln!("Invalid key in {}: {}", path.display(), e);
+                process::exit(1);
+            })
+        })
+        .unwrap_or_default();
+
+    // Decrypting with a known key needs no dictionary, so skip building a solver.
+    if let (true, Command::Solve(phrase)) = (options.decrypt, &options.command) {
+        let (phrase, hints) = parse_mixed_phrase(phrase, &hints, &options);
+        println!("{}", phrase.render_marked(&hints, UNKNOWN_MARKER));
+        return;
+    }
+
+    if options.min_word_frequency.is_some()
+        && dictionary::frequencies(lists.iter().map(AsRef::as_ref)).is_empty()
+    {
+        eprintln!("--min-word-frequency needs a dictionary with frequencies");
+        process::exit(1);
+    }
+
+    let mut words = if lists.is_empty() {
+        embedded_words()
+    } else {
+        dictionary::merge(lists.iter().map(AsRef::as_ref), options.min_word_frequency)
+    };
+
+    // A solution has only common words exactly when the dictionary has only common words, so
+    // filtering the dictionary is both the simplest and the fastest way to filter solutions.
+    if let Some(percentile) = options.word_percentile {
+        let frequencies = dictionary::frequencies(lists.iter().map(AsRef::as_ref));
+        let threshold = dictionary::frequency_at_percentile(&frequencies, percentile)
+            .unwrap_or_else(|| {
+                eprintln!("--all-words-above-percentile needs a dictionary with frequencies");
+                process::exit(1);
+            });
+        words.retain(|word| frequencies.get(word).is_some_and(|&f| f >= threshold));
+    }
+
+    // Try the most common words first, since a small dictionary is much faster to build and to
+    // search, and fall back to every word only if that finds nothing. The words are picked from
+    // those the frequency filters above leave, so that the filters still apply.
+    if let (Some(n), Command::Solve(phrase)) = (options.top_words, &options.command) {
+        let frequencies = dictionary::frequencies(lists.iter().map(AsRef::as_ref));
+        if frequencies.is_empty() {
+            eprintln!("--top-words needs a dictionary with frequencies");
+            process::exit(1);
+        }
+
+        let common = dictionary::most_common(&words, &frequencies, n);
+        let (elapsed, solver) = time!(Solver::from_dictionary(&common).with_cache());
+        print_initialize(&format!("Initialize (top {} words)", n), elapsed, &options);
+        let solver = load_cache(solver, &options);
+
+        // With the cache on, solving again below reuses the work done by this check.
+        let (phrase, hints) = parse_mixed_phrase(phrase, &hints, &options);
+        let found = if hints.is_empty() {
+            !solver.solve_mappings(&phrase).is_empty()
         } else {
-            None
+            !solver.solve_mappings_with_hints(&phrase, &hints).is_empty()
+        };
+
+        if found {
+            solve(&solver, &phrase, &hints, &theme, &options);
+            save_cache(&solver, &options);
+            return;
+        }
+
+        eprintln!(
+            "No solution among the {} most common words; using all words",
+            n
+        );
+    }
+
+    // Names join the dictionary so that they can be found, but only capitalized words may use
+    // them; see `proper_nouns`.
+    let names_list = options.names.as_ref().map(|path| {
+        dictionary::read(path).unwrap_or_else(|e| {
+            eprintln!("Unable to read {}: {}", path.display(), e);
+            process::exit(1);
+        })
+    });
+    let names: HashSet<_> = names_list
+        .iter()
+        .flat_map(|list| list.split_whitespace())
+        .collect();
+    let common: HashSet<_> = if names.is_empty() {
+        HashSet::new()
+    } else {
+        words.iter().copied().collect()
+    };
+    words.extend(names.iter().filter(|name| !common.contains(*name)));
+
+    let (elapsed, (solver, build_profile)) = time!(Solver::from_dictionary_profiled(&words));
+    print_initialize("Initialize", elapsed, &options);
+    let solver = load_cache(solver, &options);
+
+    if options.profile {
+        println!("  Pattern index: {:?}", build_profile.pattern_index);
+        println!("  Position index: {:?}", build_profile.position_index);
+    }
+
+    match &options.command {
+        Command::Solve(phrase) if !names.is_empty() => {
+            // Brackets go once their letters are among the hints, leaving the capitals that mark
+            // names.
+            let text = decode_phrase(phrase, &options);
+            let (_, hints) = plaintext_hints(&text, &hints);
+            let text = text.replace(['[', ']'], "");
+            proper_nouns(&solver, &text, &hints, &names, &common, &options)
+        }
+        Command::Solve(phrase) if options.shortlist => {
+            // Without frequencies, the shortlist would be in alphabetical order alone.
+            let frequencies = dictionary::frequencies(lists.iter().map(AsRef::as_ref));
+            if frequencies.is_empty() {
+                eprintln!("--shortlist needs a dictionary with frequencies");
+                process::exit(1);
+            }
+            shortlist(&solver, &parse_phrase(phrase, &options), &frequencies);
+        }
+        Command::Solve(phrase) => {
+            let (phrase, hints) = parse_mixed_phrase(phrase, &hints, &options);
+            solve(&solver, &phrase, &hints, &theme, &options);
+        }
+        Command::SameKey(a, b) => same_key(
+            &solver,
+            &parse_phrase(a, &options),
+            &parse_phrase(b, &options),
+        ),
+        Command::Combo(phrase) => combo(&solver, &parse_phrase(phrase, &options)),
+        Command::Play(phrase) => {
+            let (phrase, hints) = parse_mixed_phrase(phrase, &hints, &options);
+            let (stdin, stdout) = (io::stdin(), io::stdout());
+            if let Err(e) = play::play(&solver, &phrase, &hints, stdin.lock(), stdout.lock()) {
+                eprintln!("Unable to play: {}", e);
+                process::exit(1);
+            }
+        }
+        Command::ExplainWord(word) => explain_word(&solver, &parse_phrase(word, &options)),
+        Command::Serve(addr) => {
+            let listener = TcpListener::bind(addr)
+                .and_then(|listener| {
+                    println!("Listening on {}", listener.local_addr()?);
+                    Ok(listener)
+                })
+                .unwrap_or_else(|e| {
+                    eprintln!("Unable to serve on {}: {}", addr, e);
+                    process::exit(1);
+                });
+            serve::serve(&solver, listener, options.strip);
         }
     }
+
+    save_cache(&solver, &options);
 }
 
-impl AsRef<str> for Phrase {
-    fn as_ref(&self) -> &str {
-        &self.0
+/// Reports how long building a solver took, keeping stdout clean for machine-readable output.
+fn print_initialize(label: &str, elapsed: Duration, options: &Options) {
+    if options.is_machine_readable() {
+        eprintln!("{}: {:?}", label, elapsed);
+    } else {
+        println!("{}: {:?}", label, elapsed);
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Hash)]
-struct Pattern(Vec<u8>);
+/// Turns on caching if a cache file was given, starting from the file if it exists.
+fn load_cache<'w>(solver: Solver<'w>, options: &Options) -> Solver<'w> {
+    use std::{fs, io, process};
+
+    match &options.cache_file {
+        Some(path) if path.exists() => fs::File::open(path)
+            .and_then(|file| solver.with_cache_from(io::BufReader::new(file)))
+            .unwrap_or_else(|e| {
+                eprintln!("Unable to load cache from {}: {}", path.display(), e);
+                process::exit(1);
+            }),
+        Some(_) => solver.with_cache(),
+        None => solver,
+    }
+}
 
-impl Pattern {
-    fn from_str(s: &str) -> Self {
-        let mut next_symbol = 0;
-        let mut symbols = Vec::new();
-        let mut symbol_map = HashMap::new();
+fn save_cache(solver: &Solver, options: &Options) {
+    use std::{fs, io};
 
-        for u in s.bytes() {
-            symbols.push(*symbol_map.entry(u).or_insert_with(|| {
-                let insert = next_symbol;
-                next_symbol += 1;
-                insert
-            }));
+    if let Some(path) = &options.cache_file {
+        eprintln!("Cache hits: {}", solver.cache_hits());
+        let saved =
+            fs::File::create(path).and_then(|file| solver.write_cache(io::BufWriter::new(file)));
+        if let Err(e) = saved {
+            eprintln!("Unable to save cache to {}: {}", path.display(), e);
         }
-
-        Pattern(symbols)
     }
 }
 
-#[derive(Debug, Default)]
-struct Solver<'words> {
-    words_by_pattern: HashMap<Pattern, HashSet<&'words str>>,
-    words_by_character_and_index: HashMap<usize, HashMap<u8, HashSet<&'words str>>>,
+fn parse_phrase(s: &str, options: &Options) -> Phrase {
+    let s = decode_phrase(s, options);
+    let phrase = if options.strip {
+        Phrase::from_str_stripped(&s)
+    } else {
+        Phrase::from_str(&s)
+    };
+
+    phrase.unwrap_or_else(|e| phrase_error(e))
 }
 
-impl<'words> Solver<'words> {
-    fn from_dictionary(words: &[&'words str]) -> Self {
-        let mut solver = Solver::default();
+/// Reads a phrase to be solved, which may mark plaintext segments with brackets, and adds the
+/// letters those segments fix to the hints.
+fn parse_mixed_phrase(
+    s: &str,
+    hints: &HashMap<u8, u8>,
+    options: &Options,
+) -> (Phrase, HashMap<u8, u8>) {
+    if options.strip {
+        return (parse_phrase(s, options), hints.clone());
+    }
 
-        for &word in words {
-            solver
-                .words_by_pattern
-                .entry(Pattern::from_str(word))
-                .or_default()
-                .insert(word);
+    plaintext_hints(&decode_phrase(s, options), hints)
+}
 
-            for (idx, u) in word.bytes().enumerate() {
-                solver
-                    .words_by_character_and_index
-                    .entry(idx)
-                    .or_default()
-                    .entry(u)
-                    .or_default()
-                    .insert(word);
+/// Reads a decoded phrase as `parse_mixed_phrase` does.
+fn plaintext_hints(s: &str, hints: &HashMap<u8, u8>) -> (Phrase, HashMap<u8, u8>) {
+    use std::process;
+
+    let (phrase, fixed) = Phrase::from_str_with_plaintext(s).unwrap_or_else(|e| phrase_error(e));
+    let mut hints = hints.clone();
+    for (cipher, plain) in fixed {
+        match hints.insert(cipher, plain) {
+            Some(mapped) if mapped != plain => {
+                eprintln!(
+                    "Plaintext letter {} contradicts the key, which maps it to {}",
+                    cipher as char, mapped as char
+                );
+                process::exit(1);
             }
+            _ => (),
+        }
+    }
+
+    (phrase, hints)
+}
+
+/// Decodes a phrase that was given base64 or hex encoded, saying so on stderr.
+fn decode_phrase<'a>(s: &'a str, options: &Options) -> Cow<'a, str> {
+    match decode::decode(s, options.decode) {
+        Ok((text, Some(encoding))) => {
+            eprintln!("Decoded {} input: {}", encoding, text);
+            text
+        }
+        Ok((text, None)) => text,
+        Err(encoding) => {
+            eprintln!("Unable to decode the phrase as {}", encoding);
+            std::process::exit(1);
         }
+    }
+}
+
+fn phrase_error(e: cryptid::PhraseError) -> ! {
+    use cryptid::PhraseError;
 
-        solver
+    match e {
+        PhraseError::NotAscii => eprintln!("Provide an ascii phrase, would you?"),
+        PhraseError::NonLetter(_) => {
+            eprintln!("Invalid phrase: {}", e);
+            eprintln!("Use --strip to remove punctuation before solving.");
+        }
+        PhraseError::UnbalancedBracket => {
+            eprintln!("Invalid phrase: {}", e);
+            eprintln!("Plaintext segments are written [like this].");
+        }
     }
+    std::process::exit(1);
+}
+
+fn solve(
+    solver: &Solver,
+    phrase: &Phrase,
+    hints: &HashMap<u8, u8>,
+    theme: &HashSet<&str>,
+    options: &Options,
+) {
+    let solve_mappings = || {
+        if hints.is_empty() {
+            solver.solve_mappings(phrase)
+        } else {
+            solver.solve_mappings_with_hints(phrase, hints)
+        }
+    };
 
-    fn words_by_pattern(&self, word: &str) -> HashSet<&'words str> {
-        let pattern = Pattern::from_str(word);
-        self.words_by_pattern
-            .get(&pattern)
-            .cloned()
-            .unwrap_or_default()
+    if analysis::is_likely_homophonic(phrase) {
+        eprintln!(
+            "Warning: letter frequencies are too flat for a simple substitution; this may be a \
+             homophonic cipher, which cannot be solved here"
+        );
     }
 
-    fn words_by_character_and_index(&self, u: u8, idx: usize) -> Option<&HashSet<&'words str>> {
-        self.words_by_character_and_index
-            .get(&idx)
-            .and_then(|by_char| by_char.get(&u))
+    if options.affine {
+        match cryptid::affine::detect_affine(phrase) {
+            Some(((a, b), solution)) => println!("Affine (a = {}, b = {}): {}", a, b, solution),
+            None => println!("No affine key produces English"),
+        }
+        return;
     }
 
-    // FIXME: use internal iteration to print solutions as they are discovered.
-    fn solve<'a>(&self, phrase: &'a Phrase) -> impl Iterator<Item = String> + 'a {
-        // FIXME: this part is only going to work for "properly" formatted cryptograms--which is
-        // to say the kind that don't have punctuation or other non-letter characters.
-        let encrypted_words: HashSet<_> = phrase.as_ref().split_whitespace().collect();
-        let encrypted_words: Vec<_> = encrypted_words.into_iter().collect();
-        let letter_mappings = self.guess(HashMap::new(), &encrypted_words);
+    if options.rail_fence {
+        /// Longer phrases could use more rails, but few puzzles do.
+        const MAX_RAILS: usize = 10;
 
-        letter_mappings.into_iter().map(move |mapping| {
-            phrase
-                .as_ref()
-                .bytes()
-                .map(|u| mapping.get(&u).copied().unwrap_or(u) as char)
-                .collect()
-        })
+        match solver.detect_railfence(phrase, MAX_RAILS) {
+            Some((rails, solution)) => println!("Rail fence ({} rails): {}", rails, solution),
+            None => println!("No rail count produces English"),
+        }
+        return;
     }
 
-    fn guess(&self, mapping: HashMap<u8, u8>, encrypted_words: &[&str]) -> Vec<HashMap<u8, u8>> {
-        use std::cmp::Reverse;
+    if options.estimate {
+        let (elapsed, (estimate, uniqueness)) = time!((
+            solver.estimate_solution_count(phrase),
+            solver.estimate_uniqueness(phrase)
+        ));
+        println!("At most {} solutions", estimate);
+        println!("Chance of a unique solution: {:.0}%", uniqueness * 100.0);
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
 
-        let mut encrypted_words: Vec<_> = encrypted_words
-            .iter()
-            .map(|word| {
-                let candidate_matches = self.find_candidate_matches(word, &mapping);
-                (word, candidate_matches)
-            })
-            .collect();
+    if options.first {
+        let (elapsed, solution) = time!(solver.solve_first(phrase, hints));
+        if options.verify {
+            verify_solutions(phrase, solution.as_slice());
+        }
+        match solution {
+            Some(solution) => println!("{}", solution),
+            None => println!("No solution found"),
+        }
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
 
-        encrypted_words.sort_by_key(|pair| Reverse(pair.1.len()));
+    if options.profile {
+        let (elapsed, (mut solutions, profile)) = time!(solver.solve_profiled(phrase, hints));
+        if options.verify {
+            verify_solutions(phrase, &solutions);
+        }
+        solutions.sort();
+        print_solutions(&solutions, options);
+
+        println!("Elapsed: {:?}", elapsed);
+        println!("  First candidate pass: {:?}", profile.first_pass);
+        println!("  Recursion: {:?}", profile.recursion);
+        for (depth, branches) in profile.branching.iter().enumerate() {
+            println!("    Depth {}: {} branches", depth, branches);
+        }
+        return;
+    }
 
-        match encrypted_words.pop() {
-            None => vec![mapping],
-            Some((encrypted_word, candidate_words)) => {
-                let mut candidate_mappings = HashMap::new();
+    // The graph is the whole of the output, so that it can be piped straight into `dot`.
+    if options.dot {
+        let (solutions, dot) = solver.solve_with_search_tree(phrase, hints);
+        if options.verify {
+            verify_solutions(phrase, &solutions);
+        }
+        print!("{}", dot);
+        eprintln!("Solutions: {}", solutions.len());
+        return;
+    }
 
-                for &word in &candidate_words {
-                    if let Some(mapping) = self.try_extend_mapping(word, encrypted_word, &mapping) {
-                        candidate_mappings.insert(word, mapping);
-                    }
-                }
+    let score = |solution: &str| {
+        let mut score = rank::score_with_theme(solution, theme);
+        if options.short_words {
+            score += rank::short_word_bonus(solution);
+        }
+        score
+    };
+    let solve_ranked = || {
+        let solutions = solve_mappings();
+        if options.verify {
+            verify(phrase, &solutions);
+        }
+        let mut solutions = rank::rank_by(
+            solutions.iter().map(|mapping| phrase.render(mapping)),
+            score,
+        );
+        if let Some(seed) = options.seed {
+            rank::shuffle_ties(&mut solutions, seed);
+        }
+        solutions
+    };
 
-                let encrypted_words: Vec<_> =
-                    encrypted_words.iter().map(|&(&word, _)| word).collect();
+    if options.format == Format::Csv {
+        let (elapsed, mut solutions) = time!(solve_ranked());
+        solutions.truncate(options.top.unwrap_or(solutions.len()));
 
-                candidate_mappings
-                    .into_iter()
-                    .flat_map(move |(_, mapping)| self.guess(mapping, &encrypted_words))
-                    .collect()
-            }
+        let stdout = std::io::stdout();
+        if let Err(e) = csv::write(stdout.lock(), phrase, &solutions) {
+            eprintln!("Unable to write csv: {}", e);
         }
+
+        eprintln!("Elapsed: {:?}", elapsed);
+        return;
     }
 
-    fn find_candidate_matches(
-        &self,
-        word: &str,
-        mapping: &HashMap<u8, u8>,
-    ) -> HashSet<&'words str> {
-        let mut candidates = self.words_by_pattern(word);
+    if !options.emit.is_empty() {
+        let (elapsed, mappings) = time!(solve_mappings());
+        if options.verify {
+            verify(phrase, &mappings);
+        }
 
-        for (idx, u) in word.bytes().enumerate() {
-            if let Some(&mapped_char) = mapping.get(&u) {
-                if let Some(other_candidates) = self.words_by_character_and_index(mapped_char, idx)
-                {
-                    candidates.retain(|x| other_candidates.contains(x));
+        for (solution, mapping) in render_sorted(phrase, &mappings) {
+            for emit in &options.emit {
+                match emit {
+                    Emit::Text => println!("{}", solution),
+                    Emit::Key => println!("{}", key::key_string(mapping)),
+                    Emit::Json => println!("{}", key::key_json(mapping)),
                 }
             }
+            println!();
         }
 
-        candidates
+        eprintln!("Elapsed: {:?}", elapsed);
+        return;
     }
 
-    /// Attempts to extend mapping based on an encrypted word and a candidate solution.
-    fn try_extend_mapping(
-        &self,
-        word: &str,
-        encrypted_word: &str,
-        mapping: &HashMap<u8, u8>,
-    ) -> Option<HashMap<u8, u8>> {
-        let mut new_mapping = HashMap::new();
+    if let Some(template) = &options.template {
+        let (elapsed, solutions) = time!(solve_ranked());
+        solutions
+            .iter()
+            .take(options.top.unwrap_or(solutions.len()))
+            .for_each(|(solution, score)| {
+                println!("{}", template::render(template, phrase, solution, *score))
+            });
 
-        for (u_encoded, u_decoded) in encrypted_word.bytes().zip(word.bytes()) {
-            if let Some(&mapped_char) = new_mapping.get(&u_encoded) {
-                if mapped_char != u_decoded {
-                    return None;
-                }
-            }
+        eprintln!("Elapsed: {:?}", elapsed);
+        return;
+    }
 
-            if let Some(&mapped_char) = mapping.get(&u_encoded) {
-                if mapped_char != u_decoded {
-                    return None;
+    // A theme, short word bias or seed only affects ranking, so asking for one implies ranked
+    // output.
+    let ranked = options.top.is_some()
+        || options.theme.is_some()
+        || options.short_words
+        || options.seed.is_some();
+
+    if let Some(max_memory) = options.max_memory {
+        // Each solution costs roughly its text plus the bookkeeping around it.
+        let solution_size = std::mem::size_of::<(String, f64)>() + phrase.as_ref().len();
+        let limit = max_memory.saturating_mul(1024 * 1024) / solution_size;
+        if limit < 2 {
+            eprintln!(
+                "--max-memory {} is too little to hold two solutions",
+                max_memory
+            );
+            std::process::exit(1);
+        }
+
+        let (elapsed, (mut solutions, truncated)) = time!({
+            let mut solutions = rank::BoundedSolutions::new(limit, score);
+            solver.for_each_mapping(phrase, hints, |mapping| {
+                if options.verify {
+                    verify(phrase, std::iter::once(&mapping));
                 }
-            }
+                solutions.push(phrase.render(&mapping))
+            });
+            solutions.finish()
+        });
+        if let Some(seed) = options.seed {
+            rank::shuffle_ties(&mut solutions, seed);
+        }
 
-            new_mapping.insert(u_encoded, u_decoded);
+        if truncated {
+            eprintln!(
+                "Too many solutions for --max-memory; keeping only the {} best",
+                solutions.len()
+            );
         }
 
-        mapping.iter().for_each(|(&k, &v)| {
-            // This weirdness should avoid me re-inserting anything already in the map, which will
-            // in turn avoid overwriting anything by mistake. Although I think that should be
-            // impossible because of the code above.
-            new_mapping.entry(k).or_insert(v);
-        });
+        if ranked || truncated {
+            solutions
+                .iter()
+                .take(options.top.unwrap_or(solutions.len()))
+                .for_each(|(solution, score)| println!("{:.4} {}", score, solution));
+        } else {
+            let mut solutions: Vec<_> = solutions
+                .into_iter()
+                .map(|(solution, _)| solution)
+                .collect();
+            solutions.sort();
+            print_solutions(&solutions, options);
+        }
 
-        // Test for mistakenly mapping multiple characters to one character.
-        let value_set: HashSet<u8> = new_mapping.values().cloned().collect();
-        if value_set.len() != new_mapping.len() {
-            return None;
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
+
+    if ranked {
+        let (elapsed, solutions) = time!(solve_ranked());
+        solutions
+            .iter()
+            .take(options.top.unwrap_or(solutions.len()))
+            .for_each(|(solution, score)| println!("{:.4} {}", score, solution));
+
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
+
+    if options.format == Format::PlainKeyed {
+        let (elapsed, mappings) = time!(solve_mappings());
+        if options.verify {
+            verify(phrase, &mappings);
         }
 
-        Some(new_mapping)
+        for (solution, mapping) in render_sorted(phrase, &mappings) {
+            println!("{} | {}", solution, key::key_pairs(mapping));
+        }
+
+        eprintln!("Elapsed: {:?}", elapsed);
+        return;
     }
-}
 
-fn main() {
-    use std::env;
+    // Letter counts are shown as part of the grid, so asking for them implies one.
+    if options.format == Format::Grid || options.letter_counts {
+        let (elapsed, mappings) = time!(solve_mappings());
+        if options.verify {
+            verify(phrase, &mappings);
+        }
 
-    let phrase = env::args()
-        .nth(1)
-        .and_then(Phrase::from_str)
-        .expect("Provide a phrase, would you?");
+        for (solution, mapping) in render_sorted(phrase, &mappings) {
+            let grid = if options.letter_counts {
+                key::key_grid_with_counts(phrase.as_ref(), mapping)
+            } else {
+                key::key_grid(mapping)
+            };
+            println!("{}\n{}\n", solution, grid);
+        }
 
-    // Enable1.txt does not include words like A or I. It may be preferable to employ a custom
-    // word list or, alternatively, /usr/share/dict/words
-    let words: Vec<_> = include_str!("../resources/enable1.txt")
-        .split_whitespace()
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
+
+    if let Some(cap) = options.per_first_word {
+        let (elapsed, mut solutions) = time!(solver.solve_with_first_word_cap(phrase, hints, cap));
+        if options.verify {
+            verify_solutions(phrase, &solutions);
+        }
+        solutions.sort();
+        print_solutions(&solutions, options);
+
+        println!("Elapsed: {:?}", elapsed);
+        return;
+    }
+
+    let (elapsed, mappings) = time!(solve_mappings());
+    if options.verify {
+        verify(phrase, &mappings);
+    }
+
+    let mut solutions: Vec<_> = mappings
+        .iter()
+        .map(|mapping| phrase.render(mapping))
         .collect();
+    solutions.sort();
+    print_solutions(&solutions, options);
+
+    println!("Elapsed: {:?}", elapsed);
+}
+
+/// Renders each mapping's solution, pairing solutions with their mappings in sorted order.
+fn render_sorted<'m>(
+    phrase: &Phrase,
+    mappings: &'m [HashMap<u8, u8>],
+) -> Vec<(String, &'m HashMap<u8, u8>)> {
+    let mut solutions: Vec<_> = mappings
+        .iter()
+        .map(|mapping| (phrase.render(mapping), mapping))
+        .collect();
+    solutions.sort_by(|a, b| a.0.cmp(&b.0));
+    solutions
+}
 
-    let (elapsed, solver) = time!(Solver::from_dictionary(&words));
-    println!("Initialize: {:?}", elapsed);
+fn print_solutions(solutions: &[String], options: &Options) {
+    match options.group_by {
+        Some(GroupBy::FirstWord) => {
+            for (first_word, group) in group_by_first_word(solutions) {
+                println!("{} ({})", first_word, group.len());
+                group
+                    .iter()
+                    .for_each(|solution| println!("    {}", solution));
+            }
+        }
 
-    let (elapsed, mut solutions) = time!(solver.solve(&phrase).collect::<Vec<_>>());
+        None => solutions
+            .iter()
+            .for_each(|solution| println!("{}", solution)),
+    }
+}
+
+/// Checks that each key turns its decryption back into the phrase, warning about any that do not.
+///
+/// A key that fails this check points to a bug in the solver.
+fn verify<'m>(phrase: &Phrase, mappings: impl IntoIterator<Item = &'m HashMap<u8, u8>>) {
+    for mapping in mappings {
+        if !key::round_trips(phrase.as_ref(), mapping) {
+            eprintln!(
+                "Warning: {} does not encrypt back to {}",
+                phrase.render(mapping),
+                phrase.as_ref()
+            );
+        }
+    }
+}
+
+/// Checks solutions found without their keys, as `verify` does, by recovering each key from the
+/// solution itself.
+fn verify_solutions(phrase: &Phrase, solutions: &[String]) {
+    for solution in solutions {
+        match key::solution_to_key(phrase.as_ref(), solution) {
+            Some(key) if key::round_trips(phrase.as_ref(), &key) => (),
+            _ => eprintln!(
+                "Warning: {} does not encrypt back to {}",
+                solution,
+                phrase.as_ref()
+            ),
+        }
+    }
+}
+
+fn same_key(solver: &Solver, a: &Phrase, b: &Phrase) {
+    let (elapsed, mut solutions) = time!(solver.solve_jointly(&[a, b]));
     solutions.sort();
     solutions
         .iter()
-        .for_each(|solution| println!("{}", solution));
+        .for_each(|solution| println!("{}", solution.join(" / ")));
+
+    println!("Elapsed: {:?}", elapsed);
+}
+
+/// Solves mixed-case text, matching capitalized words against a list of proper nouns.
+fn proper_nouns(
+    solver: &Solver,
+    text: &str,
+    hints: &HashMap<u8, u8>,
+    names: &HashSet<&str>,
+    common: &HashSet<&str>,
+    options: &Options,
+) {
+    let (elapsed, solutions) = time!(solver.solve_with_proper_nouns(text, hints, names, common));
+    let mut solutions = solutions.unwrap_or_else(|e| phrase_error(e));
+    if options.verify {
+        let phrase = Phrase::from_str(text).unwrap_or_else(|e| phrase_error(e));
+        verify_solutions(&phrase, &solutions);
+    }
+    solutions.sort();
+    print_solutions(&solutions, options);
+
+    println!("Elapsed: {:?}", elapsed);
+}
+
+/// Lists the candidates for the longest word of a phrase, most frequent first.
+fn shortlist(solver: &Solver, phrase: &Phrase, frequencies: &HashMap<&str, u64>) {
+    /// Enough candidates to start from without burying the screen.
+    const MAX_CANDIDATES: usize = 20;
+
+    match solver.longest_word_shortlist(phrase, frequencies) {
+        Some((word, candidates)) => {
+            println!("{}: {} candidates", word, candidates.len());
+            for candidate in candidates.iter().take(MAX_CANDIDATES) {
+                println!("  {}", candidate);
+            }
+        }
+        None => println!("No words to list"),
+    }
+}
+
+/// Shows how a cipher word matches the dictionary, to find out why it does not.
+fn explain_word(solver: &Solver, word: &Phrase) {
+    /// Enough candidates to see what kind of words match.
+    const SAMPLE_SIZE: usize = 10;
+
+    let words: Vec<_> = word.as_ref().split_whitespace().collect();
+    let word = match words.as_slice() {
+        [word] => word,
+        _ => {
+            eprintln!("Give a single cipher word to explain");
+            std::process::exit(1);
+        }
+    };
+
+    let explanation = solver.explain_word(word, SAMPLE_SIZE);
+    println!("Word: {}", word);
+    println!("Pattern: {}", explanation.pattern);
+    println!("Candidates: {}", explanation.candidate_count);
+    for candidate in &explanation.sample {
+        println!("  {}", candidate);
+    }
+}
+
+fn combo(solver: &Solver, phrase: &Phrase) {
+    /// Every column order is tried, so the search grows factorially with the column count.
+    const MAX_COLUMNS: usize = 6;
+
+    let (elapsed, combo) = time!(solver.solve_combo(phrase, MAX_COLUMNS));
+    match combo {
+        Some(combo) => println!(
+            "{:.4} {} (columns {:?})",
+            combo.score, combo.solution, combo.columns
+        ),
+        None => println!("No solution found"),
+    }
 
     println!("Elapsed: {:?}", elapsed);
 }
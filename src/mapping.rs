@@ -0,0 +1,74 @@
+//! The letter mapping used inside the search.
+
+use hashbrown::HashMap;
+
+/// A one-to-one mapping from cipher bytes to plaintext bytes, stored as arrays indexed by byte.
+///
+/// The search probes and extends mappings in its innermost loop. Arrays make each lookup a
+/// single index, make the one-to-one check as cheap as the lookup, and make copying a mapping
+/// a flat copy instead of an allocation. Mappings are converted to and from hash maps at the
+/// edges of the search.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Mapping {
+    plain_for: [Option<u8>; 256],
+    cipher_for: [Option<u8>; 256],
+}
+
+impl Default for Mapping {
+    fn default() -> Self {
+        Mapping {
+            plain_for: [None; 256],
+            cipher_for: [None; 256],
+        }
+    }
+}
+
+impl Mapping {
+    /// Finds the plaintext byte a cipher byte stands for, if it is known.
+    pub(crate) fn get(&self, cipher: u8) -> Option<u8> {
+        self.plain_for[cipher as usize]
+    }
+
+    /// Finds the cipher byte that stands for a plaintext byte, if it is known.
+    pub(crate) fn cipher_for(&self, plain: u8) -> Option<u8> {
+        self.cipher_for[plain as usize]
+    }
+
+    /// Maps a cipher byte to a plaintext byte, returning false if that would contradict the
+    /// mapping or map two cipher bytes to the same plaintext byte.
+    pub(crate) fn try_insert(&mut self, cipher: u8, plain: u8) -> bool {
+        match (
+            self.plain_for[cipher as usize],
+            self.cipher_for[plain as usize],
+        ) {
+            (Some(mapped), _) => mapped == plain,
+            (None, Some(_)) => false,
+            (None, None) => {
+                self.plain_for[cipher as usize] = Some(plain);
+                self.cipher_for[plain as usize] = Some(cipher);
+                true
+            }
+        }
+    }
+
+    /// Iterates over the cipher and plaintext byte pairs in cipher order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (u8, u8)> + '_ {
+        self.plain_for
+            .iter()
+            .enumerate()
+            .filter_map(|(cipher, plain)| plain.map(|plain| (cipher as u8, plain)))
+    }
+
+    /// Builds a mapping from a hash map, returning `None` if the hash map maps two cipher bytes
+    /// to the same plaintext byte.
+    pub(crate) fn from_hash_map(map: &HashMap<u8, u8>) -> Option<Self> {
+        let mut mapping = Mapping::default();
+        map.iter()
+            .all(|(&cipher, &plain)| mapping.try_insert(cipher, plain))
+            .then_some(mapping)
+    }
+
+    pub(crate) fn to_hash_map(self) -> HashMap<u8, u8> {
+        self.iter().collect()
+    }
+}
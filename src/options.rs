@@ -0,0 +1,475 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+/// Options parsed from the command line.
+#[derive(Debug)]
+pub struct Options {
+    pub command: Command,
+    pub group_by: Option<GroupBy>,
+    pub per_first_word: Option<usize>,
+    pub format: Format,
+    /// Text written for each solution, with placeholders filled in.
+    pub template: Option<String>,
+    /// How the phrase is decoded before it is solved.
+    pub decode: Decode,
+    /// Forms in which to write each solution, in order.
+    pub emit: Vec<Emit>,
+    pub top: Option<usize>,
+    /// Seed for shuffling solutions that tie on score.
+    pub seed: Option<u64>,
+    /// Approximate limit, in megabytes, on the memory used to hold solutions.
+    pub max_memory: Option<usize>,
+    pub strip: bool,
+    pub affine: bool,
+    pub rail_fence: bool,
+    pub estimate: bool,
+    /// Stop at the first solution found.
+    pub first: bool,
+    pub short_words: bool,
+    pub verify: bool,
+    pub profile: bool,
+    /// Write the search tree as a Graphviz DOT graph instead of solutions.
+    pub dot: bool,
+    /// List candidates for the longest word instead of solving.
+    pub shortlist: bool,
+    pub letter_counts: bool,
+    /// Decrypt with the key file alone, without solving.
+    pub decrypt: bool,
+    pub dictionaries: Vec<PathBuf>,
+    pub dictionary_dir: Option<PathBuf>,
+    pub min_word_frequency: Option<u64>,
+    pub top_words: Option<usize>,
+    /// Percentile of word frequency that every word of a solution must be above.
+    pub word_percentile: Option<f64>,
+    pub theme: Option<PathBuf>,
+    /// Proper nouns, which capitalized cipher words are matched against.
+    pub names: Option<PathBuf>,
+    pub key_file: Option<PathBuf>,
+    pub cache_file: Option<PathBuf>,
+}
+
+/// The task to be performed.
+#[derive(Debug)]
+pub enum Command {
+    /// Solve a single phrase.
+    Solve(String),
+    /// Solve two phrases that are suspected to share a key.
+    SameKey(String, String),
+    /// Serve solutions over TCP at the given address.
+    Serve(String),
+    /// Solve a columnar transposition combined with a substitution.
+    Combo(String),
+    /// Guess the letters of a phrase one at a time, with feedback on each guess.
+    Play(String),
+    /// Show how a single cipher word matches the dictionary.
+    ExplainWord(String),
+}
+
+/// Describes how solutions should be grouped for display.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GroupBy {
+    FirstWord,
+}
+
+/// Describes how solutions should be written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Format {
+    Plain,
+    Csv,
+    /// Each solution followed by its key as a grid.
+    Grid,
+    /// Each solution and its key on one line, separated by ` | `.
+    PlainKeyed,
+}
+
+/// A form in which a solution can be written.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Emit {
+    /// The decrypted text.
+    Text,
+    /// The plaintext letter for each cipher letter from `a` to `z`.
+    Key,
+    /// The key as a JSON object.
+    Json,
+}
+
+/// How input should be decoded before it is read as a phrase.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Decode {
+    /// Decode input that looks encoded, and leave the rest alone.
+    Auto,
+    Base64,
+    Hex,
+    None,
+}
+
+#[derive(Debug)]
+pub struct OptionsError(String);
+
+impl fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Options {
+    pub fn from_args(args: impl IntoIterator<Item = String>) -> Result<Options, OptionsError> {
+        let mut args = args.into_iter();
+        let mut positional = Vec::new();
+        let mut group_by = None;
+        let mut per_first_word = None;
+        let mut format = Format::Plain;
+        let mut template = None;
+        let mut decode = Decode::Auto;
+        let mut emit = Vec::new();
+        let mut top = None;
+        let mut seed = None;
+        let mut max_memory = None;
+        let mut strip = false;
+        let mut affine = false;
+        let mut rail_fence = false;
+        let mut estimate = false;
+        let mut first = false;
+        let mut short_words = false;
+        let mut verify = false;
+        let mut profile = false;
+        let mut dot = false;
+        let mut shortlist = false;
+        let mut letter_counts = false;
+        let mut decrypt = false;
+        let mut serve = None;
+        let mut explain_word = None;
+        let mut dictionaries = Vec::new();
+        let mut dictionary_dir = None;
+        let mut min_word_frequency = None;
+        let mut top_words = None;
+        let mut word_percentile = None;
+        let mut theme = None;
+        let mut names = None;
+        let mut key_file = None;
+        let mut cache_file = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_ref() {
+                "--group-by" => {
+                    group_by = Some(match value_for(&arg, args.next())?.as_ref() {
+                        "first-word" => GroupBy::FirstWord,
+                        other => {
+                            return Err(OptionsError(format!(
+                                "Unknown grouping: {} (expected first-word)",
+                                other
+                            )))
+                        }
+                    });
+                }
+
+                "--format" => {
+                    format = match value_for(&arg, args.next())?.as_ref() {
+                        "plain" => Format::Plain,
+                        "csv" => Format::Csv,
+                        "grid" => Format::Grid,
+                        "plain-keyed" => Format::PlainKeyed,
+                        other => {
+                            return Err(OptionsError(format!(
+                                "Unknown format: {} (expected plain, csv, grid or plain-keyed)",
+                                other
+                            )))
+                        }
+                    };
+                }
+
+                "--emit" => {
+                    emit.push(match value_for(&arg, args.next())?.as_ref() {
+                        "text" => Emit::Text,
+                        "key" => Emit::Key,
+                        "json" => Emit::Json,
+                        other => {
+                            return Err(OptionsError(format!(
+                                "Unknown output: {} (expected text, key or json)",
+                                other
+                            )))
+                        }
+                    });
+                }
+
+                "--decode" => {
+                    decode = match value_for(&arg, args.next())?.as_ref() {
+                        "auto" => Decode::Auto,
+                        "base64" => Decode::Base64,
+                        "hex" => Decode::Hex,
+                        "none" => Decode::None,
+                        other => {
+                            return Err(OptionsError(format!(
+                                "Unknown decoding: {} (expected auto, base64, hex or none)",
+                                other
+                            )))
+                        }
+                    };
+                }
+
+                "--template" => template = Some(value_for(&arg, args.next())?),
+                "--per-first-word" => per_first_word = Some(number_for(&arg, args.next())?),
+                "--top" => top = Some(number_for(&arg, args.next())?),
+                "--seed" => seed = Some(number_for(&arg, args.next())?),
+                "--max-memory" => max_memory = Some(number_for(&arg, args.next())?),
+                "--strip" => strip = true,
+                "--affine" => affine = true,
+                "--rail-fence" => rail_fence = true,
+                "--estimate" => estimate = true,
+                "--first" => first = true,
+                "--short-words" => short_words = true,
+                "--verify" => verify = true,
+                "--profile" => profile = true,
+                "--dot" => dot = true,
+                "--shortlist" => shortlist = true,
+                "--letter-counts" => letter_counts = true,
+                "--decrypt" => decrypt = true,
+                "--serve" => serve = Some(value_for(&arg, args.next())?),
+                "--explain-word" => explain_word = Some(value_for(&arg, args.next())?),
+                "--dict" => dictionaries.push(value_for(&arg, args.next())?.into()),
+                "--dict-dir" => dictionary_dir = Some(value_for(&arg, args.next())?.into()),
+                "--min-word-frequency" => min_word_frequency = Some(number_for(&arg, args.next())?),
+                "--top-words" => top_words = Some(number_for(&arg, args.next())?),
+                "--all-words-above-percentile" => {
+                    let percentile: f64 = number_for(&arg, args.next())?;
+                    if !(0.0..=100.0).contains(&percentile) {
+                        return Err(OptionsError(format!(
+                            "{} expects a percentile from 0 to 100, not {}",
+                            arg, percentile
+                        )));
+                    }
+                    word_percentile = Some(percentile);
+                }
+                "--theme" => theme = Some(value_for(&arg, args.next())?.into()),
+                "--names" => names = Some(value_for(&arg, args.next())?.into()),
+                "--key-file" => key_file = Some(value_for(&arg, args.next())?.into()),
+                "--cache-file" => cache_file = Some(value_for(&arg, args.next())?.into()),
+
+                flag if flag.starts_with("--") => {
+                    return Err(OptionsError(format!("Unknown option: {}", flag)))
+                }
+
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+        let command = match (serve, explain_word, positional.next()) {
+            (Some(_), Some(_), _) => {
+                return Err(OptionsError(
+                    "--serve and --explain-word cannot be combined".into(),
+                ))
+            }
+            (Some(addr), None, None) => Command::Serve(addr),
+            (None, Some(word), None) => Command::ExplainWord(word),
+            (Some(_), None, Some(arg)) | (None, Some(_), Some(arg)) => {
+                return Err(OptionsError(format!("Unexpected argument: {}", arg)))
+            }
+            (None, None, Some(command)) if command == "same-key" => {
+                match (positional.next(), positional.next()) {
+                    (Some(a), Some(b)) => Command::SameKey(a, b),
+                    _ => return Err(OptionsError("same-key requires two phrases".into())),
+                }
+            }
+            (None, None, Some(command)) if command == "combo" => match positional.next() {
+                Some(phrase) => Command::Combo(phrase),
+                None => return Err(OptionsError("combo requires a phrase".into())),
+            },
+            (None, None, Some(command)) if command == "play" => match positional.next() {
+                Some(phrase) => Command::Play(phrase),
+                None => return Err(OptionsError("play requires a phrase".into())),
+            },
+            (None, None, Some(phrase)) => Command::Solve(phrase),
+            (None, None, None) => return Err(OptionsError("Provide a phrase, would you?".into())),
+        };
+
+        if let Some(arg) = positional.next() {
+            return Err(OptionsError(format!("Unexpected argument: {}", arg)));
+        }
+
+        let options = Options {
+            command,
+            group_by,
+            per_first_word,
+            format,
+            template,
+            decode,
+            emit,
+            top,
+            seed,
+            max_memory,
+            strip,
+            affine,
+            rail_fence,
+            estimate,
+            first,
+            short_words,
+            verify,
+            profile,
+            dot,
+            shortlist,
+            letter_counts,
+            decrypt,
+            dictionaries,
+            dictionary_dir,
+            min_word_frequency,
+            top_words,
+            word_percentile,
+            theme,
+            names,
+            key_file,
+            cache_file,
+        };
+        options.check_conflicts()?;
+        Ok(options)
+    }
+
+    /// Rejects options that the output asked for would otherwise silently ignore.
+    fn check_conflicts(&self) -> Result<(), OptionsError> {
+        let given = [
+            ("--format csv", self.format == Format::Csv),
+            ("--format grid", self.format == Format::Grid),
+            ("--format plain-keyed", self.format == Format::PlainKeyed),
+            // Letter counts are shown as part of the grid, so they go with it.
+            (
+                "--letter-counts",
+                self.letter_counts && self.format != Format::Grid,
+            ),
+            ("--template", self.template.is_some()),
+            ("--emit", !self.emit.is_empty()),
+            ("--affine", self.affine),
+            ("--rail-fence", self.rail_fence),
+            ("--estimate", self.estimate),
+            ("--first", self.first),
+            ("--profile", self.profile),
+            ("--dot", self.dot),
+            ("--shortlist", self.shortlist),
+            ("--group-by", self.group_by.is_some()),
+            ("--per-first-word", self.per_first_word.is_some()),
+            ("--top", self.top.is_some()),
+            ("--seed", self.seed.is_some()),
+            ("--theme", self.theme.is_some()),
+            ("--short-words", self.short_words),
+            ("--max-memory", self.max_memory.is_some()),
+            ("--strip", self.strip),
+            ("--top-words", self.top_words.is_some()),
+            ("--names", self.names.is_some()),
+        ];
+        let given: Vec<_> = given
+            .iter()
+            .filter(|(_, set)| *set)
+            .map(|(flag, _)| *flag)
+            .collect();
+
+        // Each output replaces the usual list of solutions, so only one can be asked for.
+        let mut outputs = given.iter().filter(|flag| OUTPUTS.contains(flag));
+        if let (Some(output), Some(other)) = (outputs.next(), outputs.next()) {
+            return Err(conflict(output, other));
+        }
+
+        // Options that only some outputs honour, with those outputs and the options they exclude.
+        let rules: &[(&[&str], &[&str], &[&str])] = &[
+            // Names are told apart by capitalization, which stripping loses, and solutions using
+            // them are simply listed.
+            (
+                &["--names"],
+                &[],
+                &[
+                    "--strip",
+                    "--top",
+                    "--seed",
+                    "--theme",
+                    "--short-words",
+                    "--max-memory",
+                    "--per-first-word",
+                    "--top-words",
+                ],
+            ),
+            // Only some outputs are ranked.
+            (
+                &["--top", "--seed", "--theme", "--short-words"],
+                &["--format csv", "--template"],
+                &[],
+            ),
+            // The cap is applied as solutions are found, which only the plain list does.
+            (
+                &["--per-first-word"],
+                &[],
+                &[
+                    "--top",
+                    "--seed",
+                    "--theme",
+                    "--short-words",
+                    "--max-memory",
+                ],
+            ),
+            // Solutions are only held within bounds while they are listed, ranked or not.
+            (&["--max-memory"], &[], &[]),
+            // The shortlist is drawn from every word the filters leave, not only the most common.
+            (&["--shortlist"], &[], &["--top-words"]),
+            // Groups are only drawn around a plain list of solutions, not a ranked one.
+            (
+                &["--group-by"],
+                &["--profile"],
+                &[
+                    "--top",
+                    "--seed",
+                    "--theme",
+                    "--short-words",
+                    "--max-memory",
+                ],
+            ),
+        ];
+        for (flags, outputs, excludes) in rules {
+            for flag in flags.iter().filter(|flag| given.contains(flag)) {
+                let other = given.iter().filter(|other| *other != flag).find(|other| {
+                    excludes.contains(other) || OUTPUTS.contains(other) && !outputs.contains(other)
+                });
+                if let Some(other) = other {
+                    return Err(conflict(flag, other));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tests whether output is meant for other programs, in which case timings go to stderr.
+    pub fn is_machine_readable(&self) -> bool {
+        matches!(self.format, Format::Csv | Format::PlainKeyed)
+            || self.dot
+            || self.template.is_some()
+            || !self.emit.is_empty()
+    }
+}
+
+/// Options that replace the usual list of solutions with some other output.
+const OUTPUTS: &[&str] = &[
+    "--format csv",
+    "--format grid",
+    "--format plain-keyed",
+    "--letter-counts",
+    "--template",
+    "--emit",
+    "--affine",
+    "--rail-fence",
+    "--estimate",
+    "--first",
+    "--profile",
+    "--dot",
+    "--shortlist",
+];
+
+fn conflict(flag: &str, other: &str) -> OptionsError {
+    OptionsError(format!("{} cannot be combined with {}", flag, other))
+}
+
+fn value_for(flag: &str, value: Option<String>) -> Result<String, OptionsError> {
+    value.ok_or_else(|| OptionsError(format!("{} requires a value", flag)))
+}
+
+fn number_for<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, OptionsError> {
+    let value = value_for(flag, value)?;
+    value
+        .parse()
+        .map_err(|_| OptionsError(format!("{} expects a number, not {}", flag, value)))
+}
@@ -0,0 +1,74 @@
+use cryptid::{key, GuessFeedback, Phrase, Solver};
+use hashbrown::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// Runs a guessing game on a phrase, reading one `cipher=plain` guess per line.
+///
+/// Each guess is checked against the solutions of the phrase: guesses that no solution agrees
+/// with are rejected, and the rest are added to the board. The game ends when every letter of the
+/// phrase has been guessed or the input runs out.
+pub fn play(
+    solver: &Solver,
+    phrase: &Phrase,
+    hints: &HashMap<u8, u8>,
+    input: impl BufRead,
+    mut out: impl Write,
+) -> io::Result<()> {
+    let mut guesses = hints.clone();
+    writeln!(
+        out,
+        "{}",
+        phrase.render_marked(&guesses, crate::UNKNOWN_MARKER)
+    )?;
+
+    for line in input.lines() {
+        let line = line?;
+        let guess = match key::parse_partial_key(&line) {
+            Ok(guess) if guess.is_empty() => continue,
+            Ok(guess) if guess.len() == 1 => guess.into_iter().next().unwrap(),
+            _ => {
+                writeln!(out, "Guess one letter at a time, as cipher=plain")?;
+                continue;
+            }
+        };
+
+        // A letter guessed again replaces its earlier guess, unless the new one is rejected.
+        let (cipher, plain) = guess;
+        let previous = guesses.remove(&cipher);
+
+        let feedback = solver.check_guess(phrase, guess, &guesses);
+        let reply = match feedback {
+            GuessFeedback::Inconsistent => "no solution agrees",
+            GuessFeedback::Consistent => "possible",
+            GuessFeedback::Forced => "the only possibility",
+        };
+        writeln!(out, "{}={}: {}", cipher as char, plain as char, reply)?;
+
+        match (feedback, previous) {
+            (GuessFeedback::Inconsistent, Some(previous)) => {
+                guesses.insert(cipher, previous);
+            }
+            (GuessFeedback::Inconsistent, None) => {}
+            _ => {
+                guesses.insert(cipher, plain);
+            }
+        }
+        writeln!(
+            out,
+            "{}",
+            phrase.render_marked(&guesses, crate::UNKNOWN_MARKER)
+        )?;
+
+        if phrase
+            .as_ref()
+            .bytes()
+            .filter(u8::is_ascii_lowercase)
+            .all(|u| guesses.contains_key(&u))
+        {
+            writeln!(out, "Solved")?;
+            break;
+        }
+    }
+
+    Ok(())
+}
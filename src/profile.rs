@@ -0,0 +1,64 @@
+//! Timings for the phases of building a solver and solving a phrase.
+
+use crate::{Constraints, Phrase, Solver};
+use hashbrown::HashMap;
+use std::{
+    cell::{Cell, RefCell},
+    time::Duration,
+    time::Instant,
+};
+
+/// Time spent building each of a solver's indexes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BuildProfile {
+    /// Indexing words by letter pattern.
+    pub pattern_index: Duration,
+    /// Indexing words by the letter at each position.
+    pub position_index: Duration,
+}
+
+/// Time spent in each phase of a solve, and the shape of the search.
+#[derive(Clone, Debug, Default)]
+pub struct SolveProfile {
+    /// Finding candidate words for every cipher word before any guesses are made.
+    pub first_pass: Duration,
+    /// Everything after the first pass: guessing words and searching recursively.
+    pub recursion: Duration,
+    /// The number of branches taken at each depth of the search, one entry per distinct cipher
+    /// word. A sudden jump shows where the search exploded.
+    pub branching: Vec<usize>,
+}
+
+impl<'words> Solver<'words> {
+    /// Solves a phrase as `solve_with_hints` does, timing each phase of the search.
+    pub fn solve_profiled(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+    ) -> (Vec<String>, SolveProfile) {
+        let first_pass = Cell::new(None);
+        let branching = RefCell::new(Vec::new());
+        let constraints = Constraints {
+            first_pass: Some(&first_pass),
+            branching: Some(&branching),
+            ..Constraints::default()
+        };
+
+        let start = Instant::now();
+        let mappings = self.guess_phrase(&constraints, hints.clone(), phrase);
+        let total = start.elapsed();
+
+        let first_pass = first_pass.get().unwrap_or_default();
+        let profile = SolveProfile {
+            first_pass,
+            recursion: total.saturating_sub(first_pass),
+            branching: branching.into_inner(),
+        };
+
+        let solutions = mappings
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect();
+        (solutions, profile)
+    }
+}
@@ -0,0 +1,74 @@
+//! Detection of the rail-fence cipher, a transposition that writes the plaintext in a zigzag
+//! across a number of rails and reads each rail off in turn.
+
+use crate::{Phrase, Solver};
+
+/// The least fraction of a decryption that dictionary words must cover for it to count as
+/// English. Words of one or two letters never count, so even the right decryption falls short of
+/// one.
+const MIN_COVERAGE: f64 = 0.75;
+
+/// Decrypts rail-fence ciphertext written across the given number of rails.
+///
+/// Every character takes part in the zigzag, spaces included, so text whose spaces were
+/// removed before encryption should have them removed before decryption too. Fewer than two
+/// rails leave the text unchanged.
+pub fn decrypt(ciphertext: &str, rails: usize) -> String {
+    let text = ciphertext.as_bytes();
+    if rails < 2 {
+        return ciphertext.to_owned();
+    }
+
+    // The rail each position of the plaintext was written on.
+    let period = 2 * (rails - 1);
+    let rail_of: Vec<_> = (0..text.len())
+        .map(|idx| {
+            let phase = idx % period;
+            phase.min(period - phase)
+        })
+        .collect();
+
+    // Each rail is a consecutive run of the ciphertext.
+    let mut starts = vec![0; rails];
+    for &rail in &rail_of {
+        if rail + 1 < rails {
+            starts[rail + 1] += 1;
+        }
+    }
+    for rail in 1..rails {
+        starts[rail] += starts[rail - 1];
+    }
+
+    rail_of
+        .into_iter()
+        .map(|rail| {
+            let u = text[starts[rail]];
+            starts[rail] += 1;
+            u as char
+        })
+        .collect()
+}
+
+impl<'words> Solver<'words> {
+    /// Tries every rail count from two to `max_rails` and returns the one whose decryption reads
+    /// best as English, along with that decryption.
+    ///
+    /// The phrase is taken to have been written without spaces, as rail-fence plaintext usually
+    /// is, so its spaces are ignored and the decryption has none. Decryptions are scored by how
+    /// much of them dictionary words cover. Returns `None` if no rail count covers enough.
+    pub fn detect_railfence(&self, phrase: &Phrase, max_rails: usize) -> Option<(usize, String)> {
+        let letters: String = phrase.as_ref().split_whitespace().collect();
+
+        let mut best: Option<(usize, String, f64)> = None;
+        for rails in 2..=max_rails.min(letters.len()) {
+            let plaintext = decrypt(&letters, rails);
+            let coverage = self.word_coverage(&plaintext);
+            if best.as_ref().is_none_or(|best| coverage > best.2) {
+                best = Some((rails, plaintext, coverage));
+            }
+        }
+
+        best.filter(|best| best.2 >= MIN_COVERAGE)
+            .map(|(rails, plaintext, _)| (rails, plaintext))
+    }
+}
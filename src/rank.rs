@@ -0,0 +1,304 @@
+use crate::{key, Phrase, Solver};
+use hashbrown::{HashMap, HashSet};
+
+/// Relative frequency of each letter in English text, in percent.
+const LETTER_FREQUENCIES: [f64; 26] = [
+    8.167, 1.492, 2.782, 4.253, 12.702, 2.228, 2.015, 6.094, 6.966, 0.153, 0.772, 4.025, 2.406,
+    6.749, 7.507, 1.929, 0.095, 5.987, 6.327, 9.056, 2.758, 0.978, 2.360, 0.150, 1.974, 0.074,
+];
+
+/// Bonus added to a solution's score for each of its words found in a theme word list.
+const THEME_WORD_BONUS: f64 = 0.5;
+
+/// Bonus added to a solution's score for each short word that is a common short word.
+const SHORT_WORD_BONUS: f64 = 0.25;
+
+/// The most common English words of two or three letters.
+const COMMON_SHORT_WORDS: [&str; 40] = [
+    "a", "i", "an", "as", "at", "be", "by", "do", "go", "he", "if", "in", "is", "it", "me", "my",
+    "no", "of", "on", "or", "so", "to", "up", "us", "we", "all", "and", "are", "but", "can", "for",
+    "had", "has", "her", "his", "not", "one", "the", "was", "you",
+];
+
+/// Scores candidate solutions, higher being more likely to be the plaintext.
+///
+/// Any scorer can be plugged into the ranking this way, from an n-gram model to one reached
+/// over FFI. Closures taking text and returning a score are language models too.
+pub trait LanguageModel {
+    fn score(&self, text: &str) -> f64;
+}
+
+impl<F: Fn(&str) -> f64> LanguageModel for F {
+    fn score(&self, text: &str) -> f64 {
+        self(text)
+    }
+}
+
+/// The built-in language model, which scores text with `score`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LetterFrequencies;
+
+impl LanguageModel for LetterFrequencies {
+    fn score(&self, text: &str) -> f64 {
+        score(text)
+    }
+}
+
+/// Scores text by how closely its letters resemble English.
+///
+/// The score is the average log probability of each letter, so higher (closer to zero) is
+/// better. Characters other than lowercase letters are ignored.
+pub fn score(text: &str) -> f64 {
+    let (total, count) =
+        text.bytes()
+            .filter(u8::is_ascii_lowercase)
+            .fold((0.0, 0), |(total, count), u| {
+                let p = LETTER_FREQUENCIES[(u - b'a') as usize] / 100.0;
+                (total + p.ln(), count + 1)
+            });
+
+    if count == 0 {
+        0.0
+    } else {
+        total / count as f64
+    }
+}
+
+/// Scores text as `score` does, with a bonus for each word that appears in the theme.
+pub fn score_with_theme(text: &str, theme: &HashSet<&str>) -> f64 {
+    let theme_words = text
+        .split_whitespace()
+        .filter(|word| theme.contains(word))
+        .count();
+
+    score(text) + theme_words as f64 * THEME_WORD_BONUS
+}
+
+/// Scores how plausible the short words in some text are.
+///
+/// Cipher words of up to three letters nearly always stand for one of a handful of very common
+/// words, so each such word earns a bonus. This is cheap to check and, for phrases with short
+/// words, often more telling than letter frequencies.
+pub fn short_word_bonus(text: &str) -> f64 {
+    let common_short_words = text
+        .split_whitespace()
+        .filter(|word| word.len() <= 3 && COMMON_SHORT_WORDS.contains(word))
+        .count();
+
+    common_short_words as f64 * SHORT_WORD_BONUS
+}
+
+impl<'words> Solver<'words> {
+    /// Solves a phrase and orders the solutions from best to worst according to a language
+    /// model. `LetterFrequencies` ranks by how English the letters look.
+    pub fn solve_ranked(&self, phrase: &Phrase, model: &dyn LanguageModel) -> Vec<(String, f64)> {
+        self.solve_ranked_by(phrase, |solution| model.score(solution))
+    }
+
+    /// Solves a phrase and ranks the solutions, favoring those that use theme words.
+    ///
+    /// The theme is only a bias: solutions without theme words are still returned, just lower.
+    pub fn solve_ranked_with_theme(
+        &self,
+        phrase: &Phrase,
+        theme: &HashSet<&str>,
+    ) -> Vec<(String, f64)> {
+        self.solve_ranked_by(phrase, |solution| score_with_theme(solution, theme))
+    }
+
+    /// Solves a phrase and orders the solutions by how closely their keys match a reference key.
+    ///
+    /// The reference gives the plaintext letter for each cipher letter from `a` to `z`; entries
+    /// that are not lowercase letters are unknown and never count against a solution. Each
+    /// solution is paired with the number of cipher letters its key maps differently.
+    pub fn solve_near_key(&self, phrase: &Phrase, reference: &[u8; 26]) -> Vec<(String, usize)> {
+        let mut solutions: Vec<_> = self
+            .solve_mappings(phrase)
+            .iter()
+            .map(|mapping| (phrase.render(mapping), key_distance(mapping, reference)))
+            .collect();
+
+        solutions.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        solutions
+    }
+
+    /// Solves a phrase with the given hints and orders the solutions by how much of the alphabet
+    /// their keys determine, most complete first, pairing each with that count.
+    ///
+    /// A key determines the letters of the phrase and of the hints, and once it maps all but one
+    /// letter, the last pair too. Every key for a phrase determines the same cipher letters, so
+    /// those that tie are ordered by how much of typical English text their plaintext letters
+    /// make up, since such a key reads more of other messages sent with it.
+    pub fn solve_by_coverage(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+    ) -> Vec<(String, usize)> {
+        let mut solutions: Vec<_> = self
+            .solve_mappings_with_hints(phrase, hints)
+            .into_iter()
+            .map(|key| {
+                let key = with_forced_pair(key);
+                let share: f64 = key
+                    .values()
+                    .filter(|u| u.is_ascii_lowercase())
+                    .map(|&u| LETTER_FREQUENCIES[(u - b'a') as usize])
+                    .sum();
+                (phrase.render(&key), key::key_coverage(&key), share)
+            })
+            .collect();
+
+        solutions.sort_by(|a, b| {
+            b.1.cmp(&a.1)
+                .then_with(|| b.2.total_cmp(&a.2))
+                .then_with(|| a.0.cmp(&b.0))
+        });
+        solutions
+            .into_iter()
+            .map(|(solution, coverage, _)| (solution, coverage))
+            .collect()
+    }
+
+    /// Solves a phrase and orders the solutions from best to worst according to `score`.
+    pub fn solve_ranked_by(
+        &self,
+        phrase: &Phrase,
+        score: impl Fn(&str) -> f64,
+    ) -> Vec<(String, f64)> {
+        rank_by(self.solve(phrase), score)
+    }
+}
+
+/// Counts the cipher letters a key maps differently from a known reference key.
+fn key_distance(key: &HashMap<u8, u8>, reference: &[u8; 26]) -> usize {
+    key.iter()
+        .filter(|&(&cipher, &plain)| {
+            cipher.is_ascii_lowercase() && {
+                let expected = reference[(cipher - b'a') as usize];
+                expected.is_ascii_lowercase() && expected != plain
+            }
+        })
+        .count()
+}
+
+/// Adds the pair of letters a key leaves open once it maps all but one letter, since a key maps
+/// letters one to one.
+fn with_forced_pair(mut key: HashMap<u8, u8>) -> HashMap<u8, u8> {
+    if key::key_coverage(&key) == 25 {
+        let cipher = (b'a'..=b'z').find(|u| !key.contains_key(u));
+        let plain = (b'a'..=b'z').find(|u| !key.values().any(|plain| plain == u));
+        if let (Some(cipher), Some(plain)) = (cipher, plain) {
+            key.insert(cipher, plain);
+        }
+    }
+    key
+}
+
+/// Decrypts a phrase with each of several keys, most complete key first, pairing each decryption
+/// with the number of cipher letters its key determines.
+///
+/// A key that determines more of the alphabet is more likely to be reusable on other messages.
+/// This is for keys gathered elsewhere, such as ones built up across several messages; see
+/// `Solver::solve_by_coverage` for the keys of a phrase's own solutions.
+pub fn rank_by_coverage(
+    phrase: &Phrase,
+    keys: impl IntoIterator<Item = HashMap<u8, u8>>,
+) -> Vec<(String, usize)> {
+    let mut solutions: Vec<_> = keys
+        .into_iter()
+        .map(|key| (phrase.render(&key), key::key_coverage(&key)))
+        .collect();
+
+    solutions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    solutions
+}
+
+/// Orders solutions from best to worst according to `score`, pairing each with its score.
+pub fn rank_by(
+    solutions: impl IntoIterator<Item = String>,
+    score: impl Fn(&str) -> f64,
+) -> Vec<(String, f64)> {
+    let mut solutions: Vec<_> = solutions
+        .into_iter()
+        .map(|solution| {
+            let score = score(&solution);
+            (solution, score)
+        })
+        .collect();
+
+    solutions.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    solutions
+}
+
+/// Shuffles each run of solutions with the same score, leaving the order of scores alone.
+///
+/// Ranked solutions that tie are otherwise in alphabetical order, which favors some over others
+/// for no reason. The shuffle depends only on the seed, so a seed always gives the same order.
+pub fn shuffle_ties(solutions: &mut [(String, f64)], seed: u64) {
+    // SplitMix64, which is plenty for picking an order and needs no dependencies.
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    };
+
+    for run in solutions.chunk_by_mut(|a, b| a.1 == b.1) {
+        for idx in (1..run.len()).rev() {
+            run.swap(idx, (next() % (idx as u64 + 1)) as usize);
+        }
+    }
+}
+
+/// Collects solutions up to a limit, then keeps only the best.
+///
+/// While under the limit, every solution is kept. Once it is reached, the collection is cut down
+/// to the best half of the limit before the next solution goes in, so no more than `limit`
+/// solutions are ever held.
+#[derive(Debug)]
+pub struct BoundedSolutions<F> {
+    limit: usize,
+    score: F,
+    solutions: Vec<(String, f64)>,
+    truncated: bool,
+}
+
+impl<F: Fn(&str) -> f64> BoundedSolutions<F> {
+    /// # Panics
+    ///
+    /// Panics if `limit` is less than two, since the best solution so far has to be held
+    /// alongside the next one to compare them.
+    pub fn new(limit: usize, score: F) -> Self {
+        assert!(limit >= 2, "a bound must hold at least two solutions");
+        BoundedSolutions {
+            limit,
+            score,
+            solutions: Vec::new(),
+            truncated: false,
+        }
+    }
+
+    pub fn push(&mut self, solution: String) {
+        if self.solutions.len() == self.limit {
+            self.sort();
+            self.solutions.truncate(self.limit / 2);
+            self.truncated = true;
+        }
+
+        let score = (self.score)(&solution);
+        self.solutions.push((solution, score));
+    }
+
+    /// Returns the solutions kept, best first, and whether any had to be dropped.
+    pub fn finish(mut self) -> (Vec<(String, f64)>, bool) {
+        self.sort();
+        (self.solutions, self.truncated)
+    }
+
+    fn sort(&mut self) {
+        self.solutions
+            .sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+}
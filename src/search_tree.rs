@@ -0,0 +1,145 @@
+use crate::{Constraints, Phrase, Solver};
+use hashbrown::HashMap;
+use std::{cell::RefCell, fmt::Write};
+
+/// The part of a search that has been explored, recorded as the search runs.
+///
+/// Node zero is the empty mapping the search starts from. Every other node is one candidate word
+/// tried for a cipher word, whether or not it fit the mapping so far.
+#[derive(Debug)]
+pub(crate) struct SearchTree {
+    nodes: Vec<Node>,
+    /// The node whose mapping is being extended.
+    current: usize,
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: usize,
+    label: String,
+    kind: NodeKind,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum NodeKind {
+    Root,
+    /// A candidate that fit, which the search went on to extend.
+    Branch,
+    /// A candidate that conflicted with the mapping so far.
+    DeadEnd,
+    /// A branch that decrypts every word.
+    Solution,
+}
+
+impl Default for SearchTree {
+    fn default() -> Self {
+        SearchTree {
+            nodes: vec![Node {
+                parent: 0,
+                label: String::from("start"),
+                kind: NodeKind::Root,
+            }],
+            current: 0,
+        }
+    }
+}
+
+impl SearchTree {
+    /// Records a candidate word tried for a cipher word under the current node, returning the new
+    /// node.
+    pub(crate) fn add(&mut self, encrypted_word: &str, word: &str, fits: bool) -> usize {
+        self.nodes.push(Node {
+            parent: self.current,
+            label: format!("{}={}", encrypted_word, word),
+            kind: if fits {
+                NodeKind::Branch
+            } else {
+                NodeKind::DeadEnd
+            },
+        });
+        self.nodes.len() - 1
+    }
+
+    /// Makes a node current, returning the node that was current before.
+    pub(crate) fn enter(&mut self, node: usize) -> usize {
+        std::mem::replace(&mut self.current, node)
+    }
+
+    /// Marks the current node as a solution.
+    pub(crate) fn solved(&mut self) {
+        let node = &mut self.nodes[self.current];
+        if node.kind == NodeKind::Branch {
+            node.kind = NodeKind::Solution;
+        }
+    }
+
+    /// Writes the tree as a Graphviz digraph.
+    ///
+    /// Dead ends are drawn dashed in red and solutions doubled in green, so that rendering the
+    /// tree shows at a glance where the search spent its effort.
+    fn to_dot(&self) -> String {
+        let mut has_children = vec![false; self.nodes.len()];
+        for node in &self.nodes[1..] {
+            has_children[node.parent] = true;
+        }
+
+        let mut dot = String::from("digraph search {\n    node [shape=box];\n");
+
+        for (idx, node) in self.nodes.iter().enumerate() {
+            let style = match node.kind {
+                NodeKind::Root => ", shape=ellipse",
+                NodeKind::Branch if has_children[idx] => "",
+                // A branch with nothing beneath it found no candidates for the next word.
+                NodeKind::Branch | NodeKind::DeadEnd => ", style=dashed, color=red",
+                NodeKind::Solution => ", peripheries=2, color=green",
+            };
+            let _ = writeln!(
+                dot,
+                "    n{} [label=\"{}\"{}];",
+                idx,
+                escape(&node.label),
+                style
+            );
+        }
+
+        for (idx, node) in self.nodes.iter().enumerate().skip(1) {
+            let _ = writeln!(dot, "    n{} -> n{};", node.parent, idx);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Escapes quotes and backslashes for a quoted DOT string.
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+impl<'words> Solver<'words> {
+    /// Solves a phrase, recording the search as a Graphviz DOT graph.
+    ///
+    /// The graph has a node for every candidate word tried, each a child of the node whose
+    /// mapping it extends. Candidates that conflict with the mapping, and those that leave no
+    /// candidates for the next word, are dead ends; branches that decrypt the whole phrase are
+    /// solutions. The graph grows with the search, so it is only practical to render for short
+    /// phrases. Hints work as they do for `solve_with_hints`, and prune the graph accordingly.
+    pub fn solve_with_search_tree(
+        &self,
+        phrase: &Phrase,
+        hints: &HashMap<u8, u8>,
+    ) -> (Vec<String>, String) {
+        let tree = RefCell::new(SearchTree::default());
+        let constraints = Constraints {
+            search_tree: Some(&tree),
+            ..Constraints::default()
+        };
+
+        let solutions = self
+            .guess_phrase(&constraints, hints.clone(), phrase)
+            .iter()
+            .map(|mapping| phrase.render(mapping))
+            .collect();
+        (solutions, tree.into_inner().to_dot())
+    }
+}
@@ -0,0 +1,59 @@
+use cryptid::{Phrase, Solver};
+use std::{
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+};
+
+/// Serves solutions to connections on a listener until the process is stopped.
+///
+/// The protocol is line based: each line a client sends is solved as a phrase, and the reply is
+/// one solution per line followed by an empty line. A phrase that cannot be read gets a single
+/// line starting with `error:` instead. Each connection is handled on its own thread, all
+/// sharing the one solver.
+pub fn serve(solver: &Solver, listener: TcpListener, strip: bool) {
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    scope.spawn(move || {
+                        if let Err(e) = handle(solver, stream, strip) {
+                            eprintln!("Connection failed: {}", e);
+                        }
+                    });
+                }
+                Err(e) => eprintln!("Unable to accept connection: {}", e),
+            }
+        }
+    });
+}
+
+fn handle(solver: &Solver, stream: TcpStream, strip: bool) -> io::Result<()> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let mut writer = BufWriter::new(stream);
+
+    for line in reader.lines() {
+        let line = line?;
+        let phrase = if strip {
+            Phrase::from_str_stripped(&line)
+        } else {
+            Phrase::from_str(&line)
+        };
+
+        match phrase {
+            Ok(phrase) => {
+                let mut solutions: Vec<_> = solver.solve(&phrase).collect();
+                solutions.sort();
+                for solution in solutions {
+                    writeln!(writer, "{}", solution)?;
+                }
+            }
+            Err(e) => writeln!(writer, "error: {}", e)?,
+        }
+
+        writeln!(writer)?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use cryptid::{key, Phrase};
+
+/// Fills in an output template for one solution.
+///
+/// `{phrase}`, `{solution}`, `{key}` and `{score}` are replaced with the phrase, the solution, its
+/// key as `cipher:plain` pairs and its score. Anything else is written as it stands.
+pub fn render(template: &str, phrase: &Phrase, solution: &str, score: f64) -> String {
+    let key = key::solution_to_key(phrase.as_ref(), solution)
+        .map(|key| key::key_pairs(&key))
+        .unwrap_or_default();
+
+    template
+        .replace("{phrase}", phrase.as_ref())
+        .replace("{solution}", solution)
+        .replace("{key}", &key)
+        .replace("{score}", &format!("{:.4}", score))
+}
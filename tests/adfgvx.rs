@@ -0,0 +1,47 @@
+use cryptid::{adfgvx, Solver};
+
+const SQUARE: &str = "na1c3h8tb2ome5wrpd4f6g7i9j0klqsuvxyz";
+const CIPHERTEXT: &str = "DGDD DAGD DGAF ADDF DADV DVFA ADVX";
+
+#[test]
+fn decrypts_known_example() {
+    assert_eq!(
+        adfgvx::decrypt(CIPHERTEXT, SQUARE, "PRIVACY").as_deref(),
+        Some("attackat1200am")
+    );
+}
+
+#[test]
+fn rejects_bad_input() {
+    assert_eq!(adfgvx::decrypt(CIPHERTEXT, "abc", "PRIVACY"), None);
+    assert_eq!(adfgvx::decrypt("DGDQ", SQUARE, "PRIVACY"), None);
+    assert_eq!(adfgvx::decrypt("DGD", SQUARE, "PRIVACY"), None);
+    assert_eq!(adfgvx::decrypt(CIPHERTEXT, SQUARE, ""), None);
+}
+
+#[test]
+fn scores_decryption_against_dictionary() {
+    let solver = Solver::from_dictionary(&["attack", "at", "am"]);
+    let (plaintext, coverage) = solver
+        .decrypt_adfgvx(CIPHERTEXT, SQUARE, "PRIVACY")
+        .unwrap();
+
+    assert_eq!(plaintext, "attackat1200am");
+    assert_eq!(coverage, 6.0 / 14.0);
+
+    let (_, wrong_key) = solver
+        .decrypt_adfgvx(CIPHERTEXT, SQUARE, "CIPHERS")
+        .unwrap();
+    assert!(wrong_key < coverage);
+}
+
+#[test]
+fn short_words_do_not_count_toward_coverage() {
+    // The decryption starts with "at", which is too short to count even at the very start.
+    let solver = Solver::from_dictionary(&["at", "am"]);
+    let (_, coverage) = solver
+        .decrypt_adfgvx(CIPHERTEXT, SQUARE, "PRIVACY")
+        .unwrap();
+
+    assert_eq!(coverage, 0.0);
+}
@@ -0,0 +1,29 @@
+use cryptid::{affine::detect_affine, Phrase};
+
+/// Encrypts lowercase text with the affine key `(a, b)`, leaving spaces alone.
+fn encrypt(text: &str, (a, b): (u8, u8)) -> String {
+    text.bytes()
+        .map(|u| match u {
+            b'a'..=b'z' => {
+                let x = u32::from(u - b'a');
+                (b'a' + ((u32::from(a) * x + u32::from(b)) % 26) as u8) as char
+            }
+            u => u as char,
+        })
+        .collect()
+}
+
+#[test]
+fn recovers_affine_key_and_plaintext() {
+    let plaintext = "the quick brown fox jumps over the lazy dog and all of the other animals";
+
+    for &key in &[(5, 8), (7, 3), (25, 12)] {
+        let phrase = Phrase::from_str(encrypt(plaintext, key)).unwrap();
+        assert_eq!(
+            detect_affine(&phrase),
+            Some((key, plaintext.to_string())),
+            "{:?}",
+            key
+        );
+    }
+}
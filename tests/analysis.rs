@@ -0,0 +1,23 @@
+use cryptid::{analysis, Phrase};
+
+#[test]
+fn flat_letter_frequencies_look_homophonic() {
+    // Every letter equally often, as a homophonic cipher tries to arrange.
+    let flat = Phrase::from_str("abcdefghijklm nopqrstuvwxyz mlkjihgfedcba zyxwvutsrqpon").unwrap();
+    assert!(analysis::index_of_coincidence(flat.as_ref()).unwrap() < 0.03);
+    assert!(analysis::is_likely_homophonic(&flat));
+
+    // A simple substitution keeps English's uneven frequencies.
+    let english = Phrase::from_str(
+        "CAM OHYONUMLMNC ONH HPRRVIPSN SR BNSJKMHWM PI CAM SNKT WVOQHPON SR CQVM KPXMQCT",
+    )
+    .unwrap();
+    assert!(!analysis::is_likely_homophonic(&english));
+}
+
+#[test]
+fn short_phrases_are_never_flagged() {
+    let short = Phrase::from_str("abcdefghij klmnopqrst").unwrap();
+    assert!(analysis::index_of_coincidence(short.as_ref()).unwrap() < 0.03);
+    assert!(!analysis::is_likely_homophonic(&short));
+}
@@ -0,0 +1,88 @@
+use cryptid::{Phrase, Solver};
+
+const WORDS: [&str; 6] = ["the", "cat", "cot", "tho", "dog", "tag"];
+
+#[test]
+fn phrases_of_the_same_shape_share_cached_solutions() {
+    let solver = Solver::from_dictionary(&WORDS).with_cache();
+    let uncached = Solver::from_dictionary(&WORDS);
+
+    let first = Phrase::from_str("xli gex").unwrap();
+    let second = Phrase::from_str("pqr stp").unwrap();
+
+    let mut solutions: Vec<_> = solver.solve(&first).collect();
+    solutions.sort();
+    assert_eq!(solver.cache_hits(), 0);
+
+    let mut cached: Vec<_> = solver.solve(&second).collect();
+    cached.sort();
+    assert_eq!(solver.cache_hits(), 1);
+
+    // The same words, but each key is rebuilt for the second phrase's own letters.
+    assert_eq!(cached, solutions);
+    let mut expected = uncached.solve_mappings(&second);
+    expected.sort_by_key(|mapping| second.render(mapping));
+    let mut mappings = solver.solve_mappings(&second);
+    mappings.sort_by_key(|mapping| second.render(mapping));
+    assert_eq!(mappings, expected);
+    assert!(mappings.iter().all(|mapping| mapping.contains_key(&b'p')));
+}
+
+#[test]
+fn saved_cache_is_reloaded_only_for_the_same_dictionary() {
+    let phrase = Phrase::from_str("xli gex").unwrap();
+    let solver = Solver::from_dictionary(&WORDS).with_cache();
+    let solutions: Vec<_> = solver.solve(&phrase).collect();
+
+    let mut saved = Vec::new();
+    solver.write_cache(&mut saved).unwrap();
+
+    // The words in another order make the same dictionary.
+    let mut reordered = WORDS;
+    reordered.reverse();
+    let reloaded = Solver::from_dictionary(&reordered)
+        .with_cache_from(&saved[..])
+        .unwrap();
+    assert_eq!(reloaded.solve(&phrase).collect::<Vec<_>>(), solutions);
+    assert_eq!(reloaded.cache_hits(), 1);
+
+    let other = Solver::from_dictionary(&WORDS[..4])
+        .with_cache_from(&saved[..])
+        .unwrap();
+    other.solve(&phrase).for_each(drop);
+    assert_eq!(other.cache_hits(), 0);
+}
+
+#[test]
+fn corrupt_cache_lengths_are_not_trusted() {
+    let solver = Solver::from_dictionary(&WORDS).with_cache();
+    let mut saved = Vec::new();
+    solver.write_cache(&mut saved).unwrap();
+
+    // One entry whose signature claims to be 4 GiB long, with nothing after it.
+    saved[16..20].copy_from_slice(&1u32.to_le_bytes());
+    saved.extend_from_slice(&u32::MAX.to_le_bytes());
+
+    let error = Solver::from_dictionary(&WORDS)
+        .with_cache_from(&saved[..])
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn cached_solutions_must_fit_their_phrase() {
+    let solver = Solver::from_dictionary(&WORDS).with_cache();
+    let mut saved = Vec::new();
+    solver.write_cache(&mut saved).unwrap();
+
+    // One entry for the shape of "ab", whose only solution gives a single letter.
+    saved[16..20].copy_from_slice(&1u32.to_le_bytes());
+    for bytes in vec![&[2, 0, 0, 0, 0, 1][..], &[1, 0, 0, 0], &[1, 0, 0, 0, b'a']].into_iter() {
+        saved.extend_from_slice(bytes);
+    }
+
+    let error = Solver::from_dictionary(&WORDS)
+        .with_cache_from(&saved[..])
+        .unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
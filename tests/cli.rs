@@ -0,0 +1,646 @@
+use hashbrown::HashMap;
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Write},
+    net::TcpStream,
+    path::PathBuf,
+    process::{self, Child, Command, Output, Stdio},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// A dictionary in a temporary file, which is removed when dropped so that even a failing test
+/// cleans up after itself.
+struct TempDict(PathBuf);
+
+impl TempDict {
+    fn new(contents: &str) -> Self {
+        let path = temp_path(".txt");
+        fs::write(&path, contents).unwrap();
+        TempDict(path)
+    }
+
+    /// Runs cryptid with this dictionary and the given arguments.
+    fn run(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_cryptid"))
+            .arg("--dict")
+            .arg(&self.0)
+            .args(args)
+            .output()
+            .unwrap()
+    }
+}
+
+impl Drop for TempDict {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+/// A running server, which is stopped when dropped.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// A directory of files, which is removed along with its contents when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(files: &[(&str, &[u8])]) -> Self {
+        let path = temp_path("");
+        fs::create_dir(&path).unwrap();
+        let dir = TempDir(path);
+        for (name, contents) in files {
+            fs::write(dir.0.join(name), contents).unwrap();
+        }
+        dir
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Picks a path in the temporary directory that no other test uses.
+fn temp_path(suffix: &str) -> PathBuf {
+    // Tests run in parallel, so each needs names of its own.
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let name = format!(
+        "cryptid-{}-{}{}",
+        process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed),
+        suffix
+    );
+    env::temp_dir().join(name)
+}
+
+#[test]
+fn template_fills_in_placeholders() {
+    let output = TempDict::new("the cat\n").run(&[
+        "--template",
+        "{phrase} => {solution} [{key}] {score} {other}",
+        "XLI GEX",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "xli gex => the cat [e:a g:c i:e l:h x:t] -2.6253 {other}\n"
+    );
+}
+
+#[test]
+fn emit_writes_each_requested_output() {
+    let output = TempDict::new("the cat\n").run(&[
+        "--emit", "text", "--emit", "key", "--emit", "json", "XLI GEX",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "the cat\n....a.c.e..h...........t..\n\
+         {\"e\":\"a\",\"g\":\"c\",\"i\":\"e\",\"l\":\"h\",\"x\":\"t\"}\n\n"
+    );
+}
+
+#[test]
+fn hex_encoded_phrase_is_decoded_then_solved() {
+    // "XLI GEX", hex encoded.
+    let output = TempDict::new("the cat\n").run(&["--emit", "text", "584c4920474558"]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cat\n\n");
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Decoded hex input: XLI GEX"));
+}
+
+#[test]
+fn percentile_filter_drops_solutions_with_rare_words() {
+    let dict = TempDict::new("the 1000\ncat 900\ndog 800\ncot 1\n");
+    let solve = |extra: &[&str]| {
+        let args: Vec<_> = extra
+            .iter()
+            .chain(&["--emit", "text", "XLI GEX"])
+            .copied()
+            .collect();
+        let output = dict.run(&args);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(solve(&[]).contains("the cot"));
+    assert_eq!(
+        solve(&["--all-words-above-percentile", "50"]),
+        "the cat\n\n"
+    );
+}
+
+#[test]
+fn profile_reports_every_phase() {
+    let output = TempDict::new("the cat\n").run(&["--profile", "XLI [c]EX"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("the cat\n"));
+    for phase in &[
+        "Pattern index",
+        "Position index",
+        "First candidate pass",
+        "Recursion",
+    ] {
+        let line = stdout
+            .lines()
+            .find(|line| line.trim_start().starts_with(phase))
+            .unwrap_or_else(|| panic!("no {} in {:?}", phase, stdout));
+        let duration = line.split(": ").nth(1).unwrap();
+        assert!(
+            duration.starts_with(|c: char| c.is_ascii_digit()),
+            "{}",
+            line
+        );
+    }
+}
+
+#[test]
+fn verify_finds_nothing_wrong_with_any_output() {
+    let dict = TempDict::new("the cat cot dog\n");
+    for args in &[
+        &["--format", "csv"][..],
+        &["--top", "2"],
+        &["--template", "{solution}"],
+        &["--max-memory", "1"],
+        &["--first"],
+        &["--per-first-word", "1"],
+    ] {
+        let args: Vec<_> = args
+            .iter()
+            .chain(&["--verify", "XLI GEX"])
+            .copied()
+            .collect();
+        let output = dict.run(&args);
+
+        assert!(output.status.success(), "{:?}", args);
+        assert!(String::from_utf8(output.stdout).unwrap().contains("the c"));
+        assert!(!String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("Warning"));
+    }
+}
+
+#[test]
+fn seed_implies_ranked_output() {
+    // Anagrams score the same, so only the seed orders them.
+    let dict = TempDict::new("act cat tac\n");
+    let solve = |extra: &[&str]| {
+        let args: Vec<_> = extra.iter().chain(&["XYZ"]).copied().collect();
+        let output = dict.run(&args);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .filter(|line| !line.starts_with("Initialize") && !line.starts_with("Elapsed"))
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    let seeded = solve(&["--seed", "3"]);
+    assert_eq!(seeded.len(), 3);
+    assert!(seeded.iter().all(|line| line.contains(' ')), "{:?}", seeded);
+    assert_ne!(solve(&["--seed", "1"]), seeded);
+    assert_eq!(solve(&["--seed", "3", "--max-memory", "1"]), seeded);
+}
+
+#[test]
+fn top_words_solves_with_common_words_or_falls_back() {
+    let dict = TempDict::new("the 1000\ncat 900\ndog 800\n");
+    let cache = TempDict::new("");
+    fs::remove_file(&cache.0).unwrap();
+
+    let output = dict.run(&[
+        "--top-words",
+        "2",
+        "--cache-file",
+        cache.0.to_str().unwrap(),
+        "XLI GEX",
+    ]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("Initialize (top 2 words): "));
+    assert!(stdout.contains("\nthe cat\n"));
+    assert!(cache.0.exists());
+
+    // The single most common word is "the", so the whole dictionary is needed.
+    let output = dict.run(&["--top-words", "1", "--format", "csv", "XLI GEX"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(!stdout.contains("Initialize"));
+    assert!(stdout.contains("the cat"));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("No solution among the 1 most common words; using all words"));
+}
+
+#[test]
+fn cache_file_serves_a_later_run() {
+    let dict = TempDict::new("the cat cot\n");
+    let cache = TempDict::new("");
+    fs::remove_file(&cache.0).unwrap();
+    let solve = || {
+        let output = dict.run(&["--cache-file", cache.0.to_str().unwrap(), "XLI GEX"]);
+        assert!(output.status.success());
+        (
+            String::from_utf8(output.stdout).unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+
+    let (first, stderr) = solve();
+    assert!(stderr.contains("Cache hits: 0"), "{}", stderr);
+    let (second, stderr) = solve();
+    assert!(stderr.contains("Cache hits: 1"), "{}", stderr);
+    let solutions = |stdout: &str| {
+        stdout
+            .lines()
+            .filter(|line| !line.starts_with("Initialize") && !line.starts_with("Elapsed"))
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(solutions(&second), ["the cat", "the cot"]);
+    assert_eq!(solutions(&second), solutions(&first));
+}
+
+#[test]
+fn top_words_are_picked_after_filtering_by_frequency() {
+    // "cat" is too rare to be kept, even though there is room for it among the top three.
+    let dict = TempDict::new("the 1000\ncot 950\ncat 900\n");
+    let output = dict.run(&[
+        "--top-words",
+        "3",
+        "--min-word-frequency",
+        "920",
+        "--emit",
+        "text",
+        "XLI GEX",
+    ]);
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cot\n\n");
+
+    let output = dict.run(&["--top-words", "2", "--shortlist", "XLI GEX"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--shortlist cannot be combined with --top-words"));
+}
+
+#[test]
+fn shortlist_lists_the_most_frequent_candidates_first() {
+    let output = TempDict::new("cat 10\ndog 30\nthe 50\n").run(&["--shortlist", "XLI GEX"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.contains("xli: 3 candidates\n  the\n  dog\n  cat\n"),
+        "{}",
+        stdout
+    );
+
+    let output = TempDict::new("cat dog the\n").run(&["--shortlist", "XLI GEX"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--shortlist needs a dictionary with frequencies"));
+}
+
+#[test]
+fn top_words_needs_frequencies() {
+    let output = TempDict::new("the cat\n").run(&["--top-words", "2", "XLI GEX"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--top-words needs a dictionary with frequencies"));
+}
+
+#[test]
+fn names_use_hints_and_reject_other_solving_options() {
+    let dict = TempDict::new("ran saw won\n");
+    let names = TempDict::new("tom dan\n");
+    let names = names.0.to_str().unwrap();
+
+    let output = dict.run(&["--names", names, "[w]YZ pqz"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\nwon ran\n"), "{}", stdout);
+    assert!(!stdout.contains("ran won"));
+
+    let output = dict.run(&["--names", names, "--strip", "Xyz pqz"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--names cannot be combined with --strip"));
+}
+
+#[test]
+fn plain_keyed_lines_split_back_into_solution_and_key() {
+    let output = TempDict::new("the cat cot\n").run(&["--format", "plain-keyed", "XLI GEX"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<_> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2);
+
+    for (line, expected) in lines.iter().zip(&["the cat", "the cot"]) {
+        let (solution, pairs) = line.split_once(" | ").unwrap();
+        assert_eq!(solution, *expected);
+
+        let key: HashMap<_, _> = pairs
+            .split_whitespace()
+            .map(|pair| {
+                let pair = pair.as_bytes();
+                assert_eq!(pair.len(), 3);
+                assert_eq!(pair[1], b':');
+                (pair[0], pair[2])
+            })
+            .collect();
+        assert_eq!(key.len(), 5);
+        let decrypted: String = "xli gex"
+            .bytes()
+            .map(|u| key.get(&u).map_or(u, |&plain| plain) as char)
+            .collect();
+        assert_eq!(decrypted, solution);
+    }
+}
+
+#[test]
+fn group_by_first_word_counts_each_group() {
+    let output =
+        TempDict::new("the tho cat cot cut\n").run(&["--group-by", "first-word", "XLI GEX"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let groups: Vec<_> = stdout
+        .lines()
+        .filter(|line| !line.starts_with("Initialize") && !line.starts_with("Elapsed"))
+        .collect();
+
+    // Larger groups come first, each followed by its solutions.
+    assert_eq!(
+        groups,
+        [
+            "the (3)",
+            "    the cat",
+            "    the cot",
+            "    the cut",
+            "tho (2)",
+            "    tho cat",
+            "    tho cut",
+        ]
+    );
+}
+
+#[test]
+fn options_are_rejected_where_they_would_be_ignored() {
+    let dict = TempDict::new("the cat\n");
+    for (args, error) in &[
+        (
+            &["--group-by", "first-word", "--top", "2"][..],
+            "--group-by cannot be combined with --top",
+        ),
+        (
+            &["--group-by", "first-word", "--format", "csv"],
+            "--group-by cannot be combined with --format csv",
+        ),
+        (
+            &["--top", "2", "--format", "plain-keyed"],
+            "--top cannot be combined with --format plain-keyed",
+        ),
+        (
+            &["--top", "2", "--format", "grid"],
+            "--top cannot be combined with --format grid",
+        ),
+        (
+            &["--theme", "theme.txt", "--letter-counts"],
+            "--theme cannot be combined with --letter-counts",
+        ),
+        (
+            &["--seed", "2", "--emit", "text"],
+            "--seed cannot be combined with --emit",
+        ),
+        (
+            &["--max-memory", "1", "--format", "csv"],
+            "--max-memory cannot be combined with --format csv",
+        ),
+        (
+            &["--max-memory", "1", "--emit", "key"],
+            "--max-memory cannot be combined with --emit",
+        ),
+        (
+            &["--per-first-word", "1", "--top", "5"],
+            "--per-first-word cannot be combined with --top",
+        ),
+        (
+            &["--per-first-word", "1", "--format", "grid"],
+            "--per-first-word cannot be combined with --format grid",
+        ),
+        (
+            &["--max-memory", "0"],
+            "--max-memory 0 is too little to hold two solutions",
+        ),
+        (
+            &["--format", "csv", "--emit", "key"],
+            "--format csv cannot be combined with --emit",
+        ),
+    ] {
+        let args: Vec<_> = args.iter().chain(&["XLI GEX"]).copied().collect();
+        let output = dict.run(&args);
+
+        assert!(!output.status.success(), "{:?}", args);
+        assert!(String::from_utf8(output.stderr).unwrap().contains(error));
+    }
+}
+
+#[test]
+fn server_answers_each_phrase_with_its_solutions() {
+    let dict = TempDict::new("the cat cot\n");
+    // Binding to port zero picks a free port, so tests never collide.
+    let mut server = Server(
+        Command::new(env!("CARGO_BIN_EXE_cryptid"))
+            .arg("--dict")
+            .arg(&dict.0)
+            .args(["--serve", "127.0.0.1:0"])
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap(),
+    );
+
+    let addr = BufReader::new(server.0.stdout.take().unwrap())
+        .lines()
+        .map(Result::unwrap)
+        .find_map(|line| line.strip_prefix("Listening on ").map(String::from))
+        .unwrap();
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(b"XLI GEX\nXLI, GEX\n").unwrap();
+
+    let mut lines = BufReader::new(stream).lines().map(Result::unwrap);
+    let mut reply =
+        || -> Vec<String> { lines.by_ref().take_while(|line| !line.is_empty()).collect() };
+
+    assert_eq!(reply(), ["the cat", "the cot"]);
+    assert_eq!(reply(), ["error: phrase contains non-letter character ','"]);
+}
+
+#[test]
+fn dict_dir_merges_every_list_and_skips_unreadable_ones() {
+    let dir = TempDir::new(&[
+        ("articles.txt", b"the a\n"),
+        ("animals.txt", b"cat dog\n"),
+        ("broken.txt", b"\xff\xfe\n"),
+        ("ignored.csv", b"hat\n"),
+    ]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_cryptid"))
+        .arg("--dict-dir")
+        .arg(&dir.0)
+        .args(["--emit", "text", "XLI GEX"])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cat\n\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Unable to read "));
+    assert!(stderr.contains("broken.txt"));
+}
+
+#[test]
+fn punctuation_suggests_strip() {
+    let dict = TempDict::new("the cat\n");
+
+    let output = dict.run(&["XLI, GEX."]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Invalid phrase: phrase contains non-letter character ','"));
+    assert!(stderr.contains("Use --strip to remove punctuation before solving."));
+
+    let output = dict.run(&["--strip", "--emit", "text", "XLI GEX."]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cat\n\n");
+}
+
+#[test]
+fn top_prints_that_many_solutions_best_first() {
+    let output = TempDict::new("the tho cat cot cut hat hit\n").run(&["--top", "3", "XLI GEX"]);
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let scores: Vec<f64> = stdout
+        .lines()
+        .filter(|line| !line.starts_with("Initialize") && !line.starts_with("Elapsed"))
+        .map(|line| line.split(' ').next().unwrap().parse().unwrap())
+        .collect();
+
+    assert_eq!(scores.len(), 3);
+    assert!(
+        scores.windows(2).all(|pair| pair[0] >= pair[1]),
+        "{:?}",
+        scores
+    );
+    assert!(stdout.contains(" the cat\n"));
+}
+
+#[test]
+fn min_word_frequency_drops_solutions_with_rare_words() {
+    let dict = TempDict::new("the 1000\ncat 900\ncot 3\n");
+    let solve = |min: &str| {
+        let output = dict.run(&["--min-word-frequency", min, "--emit", "text", "XLI GEX"]);
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert_eq!(solve("1"), "the cat\n\nthe cot\n\n");
+    assert_eq!(solve("10"), "the cat\n\n");
+}
+
+#[test]
+fn min_word_frequency_needs_frequencies() {
+    let output = TempDict::new("the cat\n").run(&["--min-word-frequency", "5", "XLI GEX"]);
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--min-word-frequency needs a dictionary with frequencies"));
+}
+
+#[test]
+fn key_file_constrains_the_solve() {
+    let dict = TempDict::new("the tho cat cot cut\n");
+
+    let key = TempDict::new("# from an earlier message\ng=c\ni=e\ne=o\n");
+    let output = dict.run(&[
+        "--key-file",
+        key.0.to_str().unwrap(),
+        "--emit",
+        "text",
+        "XLI GEX",
+    ]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "the cot\n\n");
+
+    let key = TempDict::new("g=c\ni=c\n");
+    let output = dict.run(&["--key-file", key.0.to_str().unwrap(), "XLI GEX"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line 2: c is the target of more than one letter"));
+}
+
+#[cfg(feature = "embedded-dict")]
+#[test]
+fn solves_without_dict_using_embedded_dictionary() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cryptid"))
+        .args([
+            "--format",
+            "plain-keyed",
+            "HIIHDTJSQTQRF ZJETQIEB UF TWRB UDR FRQCRK",
+        ])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.starts_with("opportunities multiply as they are seized | "));
+}
+
+#[cfg(feature = "embedded-dict")]
+#[test]
+fn embedded_dictionary_has_no_frequencies_to_filter_by() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cryptid"))
+        .args(["--min-word-frequency", "999999999", "XLI GEX"])
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("--min-word-frequency needs a dictionary with frequencies"));
+}
+
+#[cfg(not(feature = "embedded-dict"))]
+#[test]
+fn requires_dict_without_embedded_dictionary() {
+    let output = Command::new(env!("CARGO_BIN_EXE_cryptid"))
+        .arg("XLI GEX")
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("No dictionary given; use --dict or --dict-dir"));
+}
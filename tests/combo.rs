@@ -0,0 +1,32 @@
+use cryptid::{Phrase, Solver};
+
+/// Writes text into rows of the key's width and reads it out a column at a time, in key order,
+/// then shifts every letter three places along the alphabet.
+fn encrypt(plaintext: &str, columns: &[usize]) -> String {
+    let bytes = plaintext.as_bytes();
+    let transposed: Vec<_> = columns
+        .iter()
+        .flat_map(|&column| bytes.iter().skip(column).step_by(columns.len()))
+        .collect();
+
+    transposed
+        .into_iter()
+        .map(|&u| match u {
+            b'a'..=b'z' => (b'a' + (u - b'a' + 3) % 26) as char,
+            u => u as char,
+        })
+        .collect()
+}
+
+#[test]
+fn recovers_transposition_and_substitution() {
+    let solver = Solver::from_dictionary(&["the", "cat", "sat", "on", "mat", "a", "hat"]);
+    let plaintext = "the cat sat on the mat";
+    let columns = [2, 0, 3, 1];
+
+    let phrase = Phrase::from_str(encrypt(plaintext, &columns)).unwrap();
+    let combo = solver.solve_combo(&phrase, 4).unwrap();
+
+    assert_eq!(combo.solution, plaintext);
+    assert_eq!(combo.columns, columns);
+}
@@ -0,0 +1,15 @@
+//! Fixtures shared between test files.
+
+use cryptid::Solver;
+use std::sync::OnceLock;
+
+/// A solver over the full enable1 word list, built once and shared by every test that needs it.
+pub fn enable1() -> &'static Solver<'static> {
+    static SOLVER: OnceLock<Solver<'static>> = OnceLock::new();
+    SOLVER.get_or_init(|| {
+        let words: Vec<_> = include_str!("../../resources/enable1.txt")
+            .split_whitespace()
+            .collect();
+        Solver::from_dictionary(&words)
+    })
+}
@@ -0,0 +1,36 @@
+use cryptid::cryptarithm::{solve_cryptarithm, CryptarithmError};
+
+#[test]
+fn solves_send_more_money_uniquely() {
+    let solutions = solve_cryptarithm("SEND + MORE = MONEY").unwrap();
+    assert_eq!(solutions.len(), 1);
+
+    let solution = &solutions[0];
+    let number = |word: &str| {
+        word.bytes()
+            .fold(0, |n, u| n * 10 + u64::from(solution[&u]))
+    };
+    assert_eq!(number("send"), 9567);
+    assert_eq!(number("more"), 1085);
+    assert_eq!(number("money"), 10652);
+}
+
+#[test]
+fn rejects_malformed_equations() {
+    assert_eq!(
+        solve_cryptarithm("SEND + MORE").unwrap_err(),
+        CryptarithmError::Malformed
+    );
+    assert_eq!(
+        solve_cryptarithm("SEND + = MONEY").unwrap_err(),
+        CryptarithmError::Malformed
+    );
+    assert_eq!(
+        solve_cryptarithm("ABCDE + FGHIJ = KA").unwrap_err(),
+        CryptarithmError::TooManyLetters
+    );
+    assert_eq!(
+        solve_cryptarithm("ABCDEFGHIJABCDEFGHI + A = B").unwrap_err(),
+        CryptarithmError::TermTooLong
+    );
+}
@@ -0,0 +1,112 @@
+//! End-to-end tests against real cryptograms.
+//!
+//! Each cryptogram is a well-known quotation under its own key. All of them have been checked
+//! to have exactly one solution against the embedded dictionary, so the solver must find that
+//! solution and nothing else.
+
+mod common;
+
+use common::enable1 as solver;
+use cryptid::Phrase;
+
+fn assert_solves(cryptogram: &str, expected: &str) {
+    let phrase = Phrase::from_str(cryptogram).unwrap();
+    let solutions: Vec<_> = solver().solve(&phrase).collect();
+    assert_eq!(solutions, [expected]);
+}
+
+#[test]
+fn simplicity() {
+    assert_solves(
+        "JHOSUHQHRY HJ RDM TURHOWRM JASDHJRHQWRHAK",
+        "simplicity is the ultimate sophistication",
+    );
+}
+
+#[test]
+fn perseverance() {
+    assert_solves(
+        "MAXNASAXLFYA YDFVCAXN ASAXQKJBFP",
+        "perseverance conquers everything",
+    );
+}
+
+#[test]
+fn genius_and_perspiration() {
+    assert_solves(
+        "SINJOR JR UNI GIFMINX JNRGJFLXJUN LNQ NJNIXE NJNI GIFMINX GIFRGJFLXJUN",
+        "genius is one percent inspiration and ninety nine percent perspiration",
+    );
+}
+
+#[test]
+fn success_is_not_final() {
+    assert_solves(
+        "WRHHKWW XW AIO YXAVM YVXMRSK XW AIO YVOVM XO XW OJK HIRSVCK OI HIAOXARK OJVO HIRAOW",
+        "success is not final failure is not fatal it is the courage to continue that counts",
+    );
+}
+
+#[test]
+fn supreme_art_of_war() {
+    assert_solves(
+        "PGI EHTLIUI JLP ZK WJL VE PZ EHMDHI PGI INIUA WVPGZHP KVCGPVNC",
+        "the supreme art of war is to subdue the enemy without fighting",
+    );
+}
+
+#[test]
+fn happiness_from_actions() {
+    assert_solves(
+        "UWPPVCKXX VX CLD XLOKDUVCM HKWAE OWAK VD QLOKX RHLO ELBH LYC WQDVLCX",
+        "happiness is not something ready made it comes from your own actions",
+    );
+}
+
+#[test]
+fn diffusion_of_knowledge() {
+    assert_solves(
+        "CAM OHYONUMLMNC ONH HPRRVIPSN SR BNSJKMHWM PI CAM SNKT WVOQHPON SR CQVM KPXMQCT",
+        "the advancement and diffusion of knowledge is the only guardian of true liberty",
+    );
+}
+
+#[test]
+fn genius_has_limits() {
+    assert_solves(
+        "IHF MGZZFYFUVF AFIXFFU OFUGQK DUM KIQTGMGIW GK IHDI OFUGQK HDK GIK LGEGIK",
+        "the difference between genius and stupidity is that genius has its limits",
+    );
+}
+
+#[test]
+fn opportunities_multiply() {
+    assert_solves(
+        "HIIHDTJSQTQRF ZJETQIEB UF TWRB UDR FRQCRK",
+        "opportunities multiply as they are seized",
+    );
+}
+
+#[test]
+fn discipline_is_the_bridge() {
+    assert_solves(
+        "OTMJTKATGC TM PZC XSTOLC XCPHCCG LEBAM BGO BJJEFKATMZFCGP",
+        "discipline is the bridge between goals and accomplishment",
+    );
+}
+
+#[test]
+fn as_simple_as_possible() {
+    assert_solves(
+        "AZACKMRYFO NRXTWG QA DIGA IN NYDPWA IN PXNNYQWA QTM FXM NYDPWAC",
+        "everything should be made as simple as possible but not simpler",
+    );
+}
+
+#[test]
+fn triumph_of_evil() {
+    assert_solves(
+        "GFT JQPW GFIQE QTVTBBUSW DJS GFT GSIOZXF JD TCIP IB DJS EJJN ZTQ GJ NJ QJGFIQE",
+        "the only thing necessary for the triumph of evil is for good men to do nothing",
+    );
+}
@@ -0,0 +1,56 @@
+//! The binary's CSV writer, which is only reachable from here by including its source.
+
+#[path = "../src/csv.rs"]
+mod csv;
+
+use cryptid::Phrase;
+
+/// Splits CSV text into rows of fields, undoing quoting.
+fn parse(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+
+    rows
+}
+
+#[test]
+fn rows_parse_back_into_their_fields() {
+    let phrase = Phrase::from_str("xli gex").unwrap();
+    let solutions = vec![
+        ("the cat".to_string(), -2.5),
+        ("the, \"cat\"".to_string(), -3.0),
+    ];
+
+    let mut out = Vec::new();
+    csv::write(&mut out, &phrase, &solutions).unwrap();
+    let text = String::from_utf8(out).unwrap();
+
+    assert!(text.contains(",\"the, \"\"cat\"\"\","));
+    assert_eq!(
+        parse(&text),
+        [
+            vec!["phrase", "solution", "score", "key", "word_count"],
+            vec!["xli gex", "the cat", "-2.5000", "e:a g:c i:e l:h x:t", "2"],
+            vec!["xli gex", "the, \"cat\"", "-3.0000", "", "2"],
+        ]
+    );
+}
@@ -0,0 +1,174 @@
+use cryptid::{GuessFeedback, Phrase, PhraseError, Solver};
+use hashbrown::{HashMap, HashSet};
+
+#[test]
+fn letter_alternatives_counts_each_mapping() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "cut", "dog"]);
+    let phrase = Phrase::from_str("xyz").unwrap();
+    let alternatives = solver.letter_alternatives(&phrase);
+
+    assert_eq!(alternatives[&b'x'], [(b'c', 3), (b'd', 1)]);
+    assert_eq!(alternatives[&b'y'], [(b'o', 2), (b'a', 1), (b'u', 1)]);
+    assert_eq!(alternatives[&b'z'], [(b't', 3), (b'g', 1)]);
+}
+
+#[test]
+fn plaintext_segments_fix_their_letters() {
+    let solver = Solver::from_dictionary(&["the", "hat", "cat", "dog"]);
+
+    let (phrase, fixed) = Phrase::from_str_with_plaintext("[The] hxt").unwrap();
+    assert_eq!(phrase.as_ref(), "the hxt");
+    assert_eq!(fixed.len(), 3);
+    let solutions: Vec<_> = solver.solve_with_hints(&phrase, &fixed).collect();
+    assert_eq!(solutions, ["the hat"]);
+
+    let phrase = Phrase::from_str("hxt").unwrap();
+    assert!(solver.solve(&phrase).count() > 1);
+
+    assert_eq!(
+        Phrase::from_str_with_plaintext("[the hxt").unwrap_err(),
+        PhraseError::UnbalancedBracket
+    );
+}
+
+#[test]
+fn revealed_letters_constrain_the_solve() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    assert!(solver.solve(&phrase).count() > 1);
+
+    // The second letter of the second word.
+    assert_eq!(
+        solver.solve_with_revealed(&phrase, &[(5, 'A')]),
+        ["cat tag"]
+    );
+    assert!(solver
+        .solve_with_revealed(&phrase, &[(1, 'a'), (5, 'o')])
+        .is_empty());
+}
+
+#[test]
+fn longest_word_shortlist_is_sorted_by_frequency() {
+    let solver = Solver::from_dictionary(&["battle", "bottle", "cattle", "rattle", "kettle"]);
+    let phrase = Phrase::from_str("xli fexxpi").unwrap();
+    let frequencies = vec![("cattle", 900), ("battle", 300), ("kettle", 5000)]
+        .into_iter()
+        .collect();
+
+    let (word, shortlist) = solver
+        .longest_word_shortlist(&phrase, &frequencies)
+        .unwrap();
+    assert_eq!(word, "fexxpi");
+    assert_eq!(shortlist, ["cattle", "battle", "bottle", "rattle"]);
+}
+
+#[test]
+fn letter_determinacy_counts_plaintext_letters() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag"]);
+
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let determinacy = solver.letter_determinacy(&phrase);
+    assert_eq!(determinacy.len(), 4);
+    assert!(determinacy.values().all(|&count| count == 1));
+
+    let phrase = Phrase::from_str("xyz").unwrap();
+    let determinacy = solver.letter_determinacy(&phrase);
+    assert_eq!(determinacy[&b'x'], 3);
+    assert_eq!(determinacy[&b'y'], 2);
+    assert_eq!(determinacy[&b'z'], 2);
+}
+
+#[test]
+fn forced_mappings_follow_from_a_partial_mapping() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+
+    let partial = vec![(b'y', b'a')].into_iter().collect();
+    let forced = solver.forced_mappings(&phrase, &partial);
+
+    let expected = vec![(b'x', b'c'), (b'z', b't'), (b'w', b'g')]
+        .into_iter()
+        .collect();
+    assert_eq!(forced, expected);
+}
+
+#[test]
+fn deduce_vowels_finds_letters_forced_to_vowels() {
+    // "try" fits the first word alone, but no word can follow it in the second.
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag", "tog", "try"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+
+    let expected: HashSet<_> = vec![b'y'].into_iter().collect();
+    assert_eq!(solver.deduce_vowels(&phrase), expected);
+}
+
+#[test]
+fn check_guess_reports_consistency() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let mut existing = HashMap::new();
+
+    assert_eq!(
+        solver.check_guess(&phrase, (b'w', b'a'), &existing),
+        GuessFeedback::Inconsistent
+    );
+    assert_eq!(
+        solver.check_guess(&phrase, (b'x', b'c'), &existing),
+        GuessFeedback::Consistent
+    );
+
+    existing.insert(b'y', b'a');
+    assert_eq!(
+        solver.check_guess(&phrase, (b'x', b'c'), &existing),
+        GuessFeedback::Forced
+    );
+    assert_eq!(
+        solver.check_guess(&phrase, (b'x', b't'), &existing),
+        GuessFeedback::Inconsistent
+    );
+}
+
+#[test]
+fn minimal_hints_leave_exactly_one_solution() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "cut", "dog", "got", "tag", "tog"]);
+
+    for text in &["xyz", "xyz zyw", "xyz zyx"] {
+        let phrase = Phrase::from_str(text).unwrap();
+        let hints = solver.minimal_hints_for_unique(&phrase).unwrap();
+        let solutions: Vec<_> = solver.solve_with_hints(&phrase, &hints).collect();
+
+        let mut all: Vec<_> = solver.solve(&phrase).collect();
+        all.sort();
+        assert_eq!(solutions, &all[..1], "{}", text);
+        assert!(hints.len() < phrase.distinct_letter_count());
+    }
+
+    let phrase = Phrase::from_str("xyzz").unwrap();
+    assert_eq!(solver.minimal_hints_for_unique(&phrase), None);
+}
+
+#[test]
+fn vowel_hints_remove_solutions_with_consonants() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "cut", "sky", "shy", "spy"]);
+    let phrase = Phrase::from_str("xyz").unwrap();
+    assert_eq!(solver.solve(&phrase).count(), 6);
+
+    let vowels: HashSet<_> = vec![b'y'].into_iter().collect();
+    let mut solutions: Vec<_> = solver.solve_with_vowel_hints(&phrase, &vowels).collect();
+    solutions.sort();
+    assert_eq!(solutions, ["cat", "cot", "cut"]);
+}
+
+#[test]
+fn hint_viability_rejects_contradictions() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let existing: HashMap<_, _> = vec![(b'x', b'c')].into_iter().collect();
+
+    assert!(solver.hint_is_viable(&phrase, (b'y', b'a'), &existing));
+    // Once x is c, the first word is cat or cot, so y cannot be e.
+    assert!(!solver.hint_is_viable(&phrase, (b'y', b'e'), &existing));
+    // Hints that contradict the existing ones directly are never viable.
+    assert!(!solver.hint_is_viable(&phrase, (b'x', b'd'), &existing));
+    assert!(!solver.hint_is_viable(&phrase, (b'z', b'c'), &existing));
+}
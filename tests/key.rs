@@ -0,0 +1,160 @@
+use cryptid::{key, rank, Phrase, Solver};
+use hashbrown::HashMap;
+
+#[test]
+fn key_grid_with_counts_counts_each_cipher_letter() {
+    let key: HashMap<_, _> = vec![(b'a', b't'), (b'b', b'h'), (b'c', b'e')]
+        .into_iter()
+        .collect();
+    let grid = key::key_grid_with_counts("abc ac cc", &key);
+    let rows: Vec<_> = grid.lines().collect();
+
+    assert_eq!(
+        rows[0],
+        "a b c d e f g h i j k l m n o p q r s t u v w x y z"
+    );
+    assert!(rows[1].starts_with("t h e  "));
+    assert_eq!(rows[2].trim_end(), "2 1 4");
+}
+
+#[test]
+fn unknown_letters_render_as_marker() {
+    let phrase = Phrase::from_str("xli gex").unwrap();
+    let key: HashMap<_, _> = vec![(b'x', b't'), (b'l', b'h'), (b'i', b'e')]
+        .into_iter()
+        .collect();
+
+    assert_eq!(phrase.render_marked(&key, '·'), "the ··t");
+    assert_eq!(phrase.render(&key), "the get");
+}
+
+#[test]
+fn more_complete_keys_rank_first() {
+    let phrase = Phrase::from_str("xli").unwrap();
+    let sparse = key::solution_to_key("xli", "the").unwrap();
+    let mut complete = key::solution_to_key("xli", "the").unwrap();
+    complete.extend(vec![(b'e', b'a'), (b'g', b'c')]);
+
+    let ranked = rank::rank_by_coverage(&phrase, vec![sparse, complete]);
+    assert_eq!(ranked, [("the".to_string(), 5), ("the".to_string(), 3)]);
+}
+
+#[test]
+fn merge_keys_combines_compatible_keys_and_reports_conflicts() {
+    let key = |pairs: &[(u8, u8)]| -> HashMap<u8, u8> { pairs.iter().copied().collect() };
+    let a = key(&[(b'x', b't'), (b'y', b'h')]);
+
+    let merged = key::merge_keys(&a, &key(&[(b'y', b'h'), (b'z', b'e')])).unwrap();
+    assert_eq!(merged, key(&[(b'x', b't'), (b'y', b'h'), (b'z', b'e')]));
+
+    assert_eq!(
+        key::merge_keys(&a, &key(&[(b'x', b'a')])),
+        Err(key::KeyConflict::Cipher {
+            cipher: b'x',
+            plain: (b't', b'a'),
+        })
+    );
+    assert_eq!(
+        key::merge_keys(&a, &key(&[(b'z', b't')])),
+        Err(key::KeyConflict::Plain {
+            plain: b't',
+            cipher: (b'x', b'z'),
+        })
+    );
+}
+
+#[test]
+fn every_solution_of_a_phrase_round_trips() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog", "the"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let mappings = solver.solve_mappings(&phrase);
+    assert_eq!(mappings.len(), 3);
+
+    for mapping in &mappings {
+        assert!(key::round_trips(phrase.as_ref(), mapping));
+
+        let solution = phrase.render(mapping);
+        let recovered = key::solution_to_key(phrase.as_ref(), &solution).unwrap();
+        assert!(key::round_trips(phrase.as_ref(), &recovered));
+        assert_eq!(key::key_to_solution(phrase.as_ref(), &recovered), solution);
+    }
+}
+
+#[test]
+fn solution_and_key_convert_both_ways() {
+    for (cipher, plain) in &[
+        ("xli gex", "the cat"),
+        ("hiihdtjsqtqrf zjetqieb", "opportunities multiply"),
+        ("a", "i"),
+    ] {
+        let key = key::solution_to_key(cipher, plain).unwrap();
+        assert_eq!(key::key_to_solution(cipher, &key), *plain);
+        assert_eq!(
+            key::solution_to_key(cipher, &key::key_to_solution(cipher, &key)),
+            Some(key)
+        );
+    }
+
+    // Texts that no single key relates.
+    assert_eq!(key::solution_to_key("xli gex", "the"), None);
+    assert_eq!(key::solution_to_key("xx", "at"), None);
+    assert_eq!(key::solution_to_key("xy", "tt"), None);
+}
+
+#[test]
+fn key_grid_has_a_column_per_letter() {
+    let key = key::solution_to_key("xli gex", "the cat").unwrap();
+    let grid = key::key_grid(&key);
+    let rows: Vec<_> = grid.lines().collect();
+    assert_eq!(rows.len(), 2);
+
+    // Every column is two characters wide, the last without its trailing space.
+    let cipher: Vec<_> = rows[0].chars().step_by(2).collect();
+    let plain: Vec<_> = rows[1]
+        .chars()
+        .chain(std::iter::repeat(' '))
+        .step_by(2)
+        .take(26)
+        .collect();
+    assert_eq!(cipher.len(), 26);
+    assert_eq!(
+        cipher.iter().collect::<String>(),
+        "abcdefghijklmnopqrstuvwxyz"
+    );
+
+    for (&c, &p) in cipher.iter().zip(&plain) {
+        match key.get(&(c as u8)) {
+            Some(&mapped) => assert_eq!(p, mapped as char),
+            None => assert_eq!(p, ' ', "column {}", c),
+        }
+    }
+}
+
+#[test]
+fn partial_key_errors_name_their_line() {
+    let key = key::parse_partial_key("# known so far\nX=t\n\nl = H\n").unwrap();
+    assert_eq!(key.len(), 2);
+    assert_eq!(key[&b'x'], b't');
+    assert_eq!(key[&b'l'], b'h');
+
+    let error = |text: &str| key::parse_partial_key(text).unwrap_err();
+
+    let missing = error("x=t\nl h\n");
+    assert_eq!(missing.line, 2);
+    assert!(missing.message.contains("expected cipher=plain"));
+
+    let cipher_twice = error("x=t\n# comment\nx=h\n");
+    assert_eq!(cipher_twice.line, 3);
+    assert_eq!(cipher_twice.message, "x is mapped more than once");
+
+    let plain_twice = error("x=t\nl=t\n");
+    assert_eq!(plain_twice.line, 2);
+    assert_eq!(
+        plain_twice.message,
+        "t is the target of more than one letter"
+    );
+    assert_eq!(
+        plain_twice.to_string(),
+        "line 2: t is the target of more than one letter"
+    );
+}
@@ -0,0 +1,39 @@
+use cryptid::{railfence, Phrase, Solver};
+
+const CIPHERTEXT: &str = "wecrlteerdsoeefeaocaivden";
+
+#[test]
+fn decrypts_known_example() {
+    assert_eq!(
+        railfence::decrypt(CIPHERTEXT, 3),
+        "wearediscoveredfleeatonce"
+    );
+    assert_eq!(railfence::decrypt(CIPHERTEXT, 1), CIPHERTEXT);
+}
+
+#[test]
+fn detects_rail_count() {
+    let solver = Solver::from_dictionary(&["we", "are", "discovered", "flee", "at", "once"]);
+    let phrase = Phrase::from_str(CIPHERTEXT).unwrap();
+
+    assert_eq!(
+        solver.detect_railfence(&phrase, 8),
+        Some((3, String::from("wearediscoveredfleeatonce")))
+    );
+    assert_eq!(
+        solver.detect_railfence(&Phrase::from_str("xqzv jkwp").unwrap(), 8),
+        None
+    );
+}
+
+#[test]
+fn short_words_do_not_make_a_decryption_english() {
+    // "weflee" across two rails; only "flee" is long enough to count, which is too little.
+    let solver = Solver::from_dictionary(&["we", "flee"]);
+
+    assert_eq!(railfence::decrypt("wfeele", 2), "weflee");
+    assert_eq!(
+        solver.detect_railfence(&Phrase::from_str("wfeele").unwrap(), 8),
+        None
+    );
+}
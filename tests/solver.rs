@@ -0,0 +1,703 @@
+mod common;
+
+use common::enable1;
+use cryptid::{
+    rank::{self, LanguageModel},
+    Phrase, PhraseError, Solver,
+};
+use hashbrown::{HashMap, HashSet};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+#[test]
+fn filtered_solver_only_uses_matching_words() {
+    let words = ["cat", "cow", "dog", "pig", "the", "big", "red"];
+    let animals = ["cat", "cow", "dog", "pig"];
+    let solver = Solver::from_dictionary_filtered(&words, |word| animals.contains(&word));
+
+    let phrase = Phrase::from_str("xyz").unwrap();
+    let mut solutions: Vec<_> = solver.solve(&phrase).collect();
+    solutions.sort();
+
+    assert_eq!(solutions, animals);
+}
+
+#[test]
+fn sentences_are_solved_jointly_under_one_key() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag"]);
+
+    // Each sentence alone has several solutions; only one key fits both.
+    for sentence in &["Xyz.", "Zyw!"] {
+        let phrase = Phrase::from_str_stripped(sentence).unwrap();
+        assert!(solver.solve(&phrase).count() > 1);
+    }
+
+    let phrase = Phrase::from_str_stripped("Xyz.Zyw!").unwrap();
+    assert_eq!(phrase.as_ref(), "xyz zyw");
+    assert_eq!(solver.solve(&phrase).collect::<Vec<_>>(), ["cat tag"]);
+}
+
+#[test]
+fn punctuation_is_rejected_unless_stripped() {
+    assert_eq!(
+        Phrase::from_str("xli, gex!").unwrap_err(),
+        PhraseError::NonLetter(',')
+    );
+    assert_eq!(
+        Phrase::from_str("don't").unwrap_err(),
+        PhraseError::NonLetter('\'')
+    );
+    assert_eq!(
+        Phrase::from_str_stripped("Xli, gex! Don't")
+            .unwrap()
+            .as_ref(),
+        "xli  gex  dont"
+    );
+}
+
+#[test]
+fn explain_word_reports_pattern_and_candidates() {
+    let solver = Solver::from_dictionary(&["battle", "bottle", "cattle", "kettle", "rattle"]);
+    let explanation = solver.explain_word("FEXXPI", 2);
+
+    assert_eq!(explanation.pattern, "abccde");
+    assert_eq!(explanation.candidate_count, 4);
+    assert_eq!(explanation.sample, ["battle", "bottle"]);
+}
+
+#[test]
+fn seeded_shuffle_reorders_only_ties() {
+    let ranked: Vec<_> = std::iter::once((String::from("best"), 1.0))
+        .chain((0..20).map(|n| (format!("tie {:02}", n), 0.5)))
+        .collect();
+    let shuffled = |seed| {
+        let mut solutions = ranked.clone();
+        rank::shuffle_ties(&mut solutions, seed);
+        solutions
+    };
+
+    assert_eq!(shuffled(7), shuffled(7));
+    assert_ne!(shuffled(7), shuffled(8));
+    assert_ne!(shuffled(7), ranked);
+    assert_eq!(shuffled(7)[0], ranked[0]);
+}
+
+#[test]
+fn solve_first_stops_at_one_solution() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let first = solver.solve_first(&phrase, &HashMap::new()).unwrap();
+    assert!(solver.solve(&phrase).any(|solution| solution == first));
+
+    let phrase = Phrase::from_str("xyzz").unwrap();
+    assert_eq!(solver.solve_first(&phrase, &HashMap::new()), None);
+}
+
+#[test]
+fn solve_first_respects_hints() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz").unwrap();
+
+    let hints: HashMap<_, _> = vec![(b'x', b't')].into_iter().collect();
+    assert_eq!(solver.solve_first(&phrase, &hints).unwrap(), "tag");
+
+    let hints: HashMap<_, _> = vec![(b'x', b'c'), (b'y', b'c')].into_iter().collect();
+    assert_eq!(solver.solve_first(&phrase, &hints), None);
+}
+
+#[test]
+fn capitalized_words_match_proper_nouns() {
+    let names: HashSet<_> = vec!["tom", "dan"].into_iter().collect();
+    let common: HashSet<_> = vec!["ran", "saw", "won"].into_iter().collect();
+    let words: Vec<_> = names.union(&common).copied().collect();
+    let solver = Solver::from_dictionary(&words);
+
+    let mut solutions = solver
+        .solve_with_proper_nouns("Xyz pqz", &HashMap::new(), &names, &common)
+        .unwrap();
+    solutions.sort();
+    assert_eq!(solutions, ["dan won"]);
+
+    // Without capitals, the first word may be any common word.
+    let mut solutions = solver
+        .solve_with_proper_nouns("xyz pqz", &HashMap::new(), &names, &common)
+        .unwrap();
+    solutions.sort();
+    assert_eq!(solutions, ["ran won", "won ran"]);
+
+    let hints: HashMap<_, _> = vec![(b'x', b'w')].into_iter().collect();
+    let solutions = solver
+        .solve_with_proper_nouns("xyz pqz", &hints, &names, &common)
+        .unwrap();
+    assert_eq!(solutions, ["won ran"]);
+}
+
+/// Prefers words that end late in the alphabet.
+struct LastLetter;
+
+impl LanguageModel for LastLetter {
+    fn score(&self, text: &str) -> f64 {
+        text.bytes().last().map_or(0.0, f64::from)
+    }
+}
+
+#[test]
+fn custom_language_model_drives_ranking() {
+    let solver = Solver::from_dictionary(&["cat", "cow", "dog"]);
+    let phrase = Phrase::from_str("xyz").unwrap();
+    let ranked: Vec<_> = solver
+        .solve_ranked(&phrase, &LastLetter)
+        .into_iter()
+        .map(|(solution, _)| solution)
+        .collect();
+
+    assert_eq!(ranked, ["cow", "cat", "dog"]);
+}
+
+#[test]
+fn profile_reports_branching_at_each_depth() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "tag", "tog", "a"]);
+    let phrase = Phrase::from_str("xyz zyw y").unwrap();
+    let (solutions, profile) = solver.solve_profiled(&phrase, &HashMap::new());
+
+    assert_eq!(solutions, ["cat tag a"]);
+    assert_eq!(profile.branching.len(), 3);
+    assert!(profile.branching.iter().all(|&branches| branches > 0));
+}
+
+#[test]
+fn profile_phases_fit_within_the_solve_and_respect_hints() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let hints: HashMap<_, _> = vec![(b'x', b'd')].into_iter().collect();
+
+    let start = Instant::now();
+    let (solutions, profile) = solver.solve_profiled(&phrase, &hints);
+    let elapsed = start.elapsed();
+
+    assert_eq!(solutions, ["dog got"]);
+    assert!(profile.first_pass > Duration::ZERO);
+    assert!(profile.first_pass + profile.recursion <= elapsed);
+}
+
+#[test]
+fn search_tree_has_a_node_per_candidate_tried() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let (mut solutions, dot) = solver.solve_with_search_tree(&phrase, &HashMap::new());
+    solutions.sort();
+
+    assert_eq!(solutions, ["cat tag", "cot tog", "dog got"]);
+    assert!(dot.starts_with("digraph search {\n"));
+    assert!(dot.ends_with("}\n"));
+
+    // The root, six candidates for the first word and five tried for the second, of which two
+    // conflict with the first.
+    let nodes = dot.lines().filter(|line| line.contains("[label=")).count();
+    let edges = dot.lines().filter(|line| line.contains(" -> ")).count();
+    assert_eq!(nodes, 12);
+    assert_eq!(edges, nodes - 1);
+    assert_eq!(dot.matches("peripheries=2").count(), solutions.len());
+    assert_eq!(dot.matches("style=dashed").count(), 3);
+}
+
+#[test]
+fn search_tree_is_pruned_by_hints() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let hints: HashMap<_, _> = vec![(b'x', b'd')].into_iter().collect();
+    let (solutions, dot) = solver.solve_with_search_tree(&phrase, &hints);
+
+    assert_eq!(solutions, ["dog got"]);
+    let nodes = dot.lines().filter(|line| line.contains("[label=")).count();
+    assert_eq!(nodes, 3);
+}
+
+/// Finds every solution by trying every combination of words, checking each key with a plain
+/// hash map.
+fn brute_force(words: &[&str], phrase: &str) -> Vec<String> {
+    let mut solutions = vec![(Vec::new(), HashMap::new())];
+
+    for encrypted_word in phrase.split_whitespace() {
+        let mut extended = Vec::new();
+        for (plain_words, key) in &solutions {
+            for &word in words
+                .iter()
+                .filter(|word| word.len() == encrypted_word.len())
+            {
+                let mut key: HashMap<u8, u8> = key.clone();
+                let consistent = encrypted_word
+                    .bytes()
+                    .zip(word.bytes())
+                    .all(|(cipher, plain)| *key.entry(cipher).or_insert(plain) == plain);
+                let one_to_one = key.values().collect::<HashSet<_>>().len() == key.len();
+
+                if consistent && one_to_one {
+                    let mut plain_words: Vec<_> = plain_words.clone();
+                    plain_words.push(word);
+                    extended.push((plain_words, key));
+                }
+            }
+        }
+        solutions = extended;
+    }
+
+    let mut solutions: Vec<_> = solutions
+        .into_iter()
+        .map(|(plain_words, _)| plain_words.join(" "))
+        .collect();
+    solutions.sort();
+    solutions.dedup();
+    solutions
+}
+
+#[test]
+fn solutions_match_brute_force() {
+    let words = [
+        "a", "i", "an", "as", "at", "in", "is", "it", "no", "on", "to", "and", "ant", "cat", "cot",
+        "dog", "god", "not", "tan", "ten", "the", "toe", "tot", "see", "tea", "eat", "ate",
+    ];
+    let solver = Solver::from_dictionary(&words);
+
+    for phrase in [
+        "xyz zyx",
+        "ab bc xyz",
+        "abc ad aec",
+        "abb bxa a",
+        "xyz xyz yw",
+    ] {
+        let mut solutions: Vec<_> = solver.solve(&Phrase::from_str(phrase).unwrap()).collect();
+        solutions.sort();
+        assert_eq!(solutions, brute_force(&words, phrase), "{}", phrase);
+    }
+}
+
+#[test]
+fn suffix_narrows_candidates_for_its_word() {
+    let solver =
+        Solver::from_dictionary(&["king", "ring", "sing", "sang", "song", "rang", "bring"]);
+    let phrase = Phrase::from_str("abcd").unwrap();
+
+    let mut solutions = solver.solve_with_suffixes(&phrase, &[(0, "ING")]);
+    solutions.sort();
+    assert_eq!(solutions, ["king", "ring", "sing"]);
+
+    let mut solutions = solver.solve_with_suffixes(&phrase, &[(0, "ng"), (0, "ang")]);
+    solutions.sort();
+    assert_eq!(solutions, ["rang", "sang"]);
+
+    assert!(solver
+        .solve_with_suffixes(&phrase, &[(0, "ing"), (0, "ang")])
+        .is_empty());
+    assert_eq!(solver.solve_with_suffixes(&phrase, &[(3, "ing")]).len(), 6);
+}
+
+#[test]
+fn channel_delivers_every_solution_then_closes() {
+    let solver = Arc::new(Solver::from_dictionary(&[
+        "cat", "cot", "dog", "tag", "tog",
+    ]));
+    let phrase = Phrase::from_str("xyz").unwrap();
+
+    let mut received: Vec<_> = solver.solve_channel(&phrase).iter().collect();
+    received.sort();
+
+    let mut expected: Vec<_> = solver.solve(&phrase).collect();
+    expected.sort();
+    assert_eq!(received, expected);
+}
+
+#[test]
+fn word_predicate_narrows_candidates_for_its_word() {
+    let solver = Solver::from_dictionary(&["ball", "bell", "tell", "book", "seen", "feet", "wall"]);
+    let phrase = Phrase::from_str("abcc xyyz").unwrap();
+    let double_l = |word: &str| word.contains("ll");
+    let double_o = |word: &str| word.contains("oo");
+
+    let mut solutions =
+        solver.solve_with_word_predicates(&phrase, &[(0, &double_l), (1, &double_o)]);
+    solutions.sort();
+    assert_eq!(solutions, ["tell book", "wall book"]);
+}
+
+#[test]
+fn batch_parallel_matches_sequential() {
+    let phrases: Vec<_> = [
+        "JHOSUHQHRY HJ RDM TURHOWRM JASDHJRHQWRHAK",
+        "NIJBVO OBJO YAVWJB ABVB",
+    ]
+    .iter()
+    .map(|s| Phrase::from_str(s).unwrap())
+    .collect();
+
+    let sequential: Vec<Vec<_>> = phrases
+        .iter()
+        .map(|phrase| enable1().solve(phrase).collect())
+        .collect();
+
+    assert_eq!(enable1().solve_batch_parallel(&phrases), sequential);
+}
+
+#[test]
+fn words_matching_fills_in_wildcards() {
+    assert_eq!(enable1().words_matching("c.t"), ["cat", "cot", "cut"]);
+    assert_eq!(enable1().words_matching("C.T"), ["cat", "cot", "cut"]);
+    assert!(enable1().words_matching("..").contains(&"of"));
+    assert!(enable1().words_matching("q.x").is_empty());
+}
+
+#[test]
+fn unique_cryptograms_are_estimated_likelier_to_be_unique() {
+    let average = |cryptograms: &[&str]| {
+        let total: f64 = cryptograms
+            .iter()
+            .map(|s| enable1().estimate_uniqueness(&Phrase::from_str(s).unwrap()))
+            .sum();
+        total / cryptograms.len() as f64
+    };
+
+    let unique = average(&[
+        "JHOSUHQHRY HJ RDM TURHOWRM JASDHJRHQWRHAK",
+        "MAXNASAXLFYA YDFVCAXN ASAXQKJBFP",
+        "HIIHDTJSQTQRF ZJETQIEB UF TWRB UDR FRQCRK",
+        "OTMJTKATGC TM PZC XSTOLC XCPHCCG LEBAM BGO BJJEFKATMZFCGP",
+    ]);
+    let ambiguous = average(&[
+        "NIJBVO OBJO YAVWJB ABVB",
+        "XLI GEX WEX",
+        "LIPPS ASVPH",
+        "EPP MW AIPP",
+    ]);
+
+    assert!(unique > ambiguous, "{} <= {}", unique, ambiguous);
+}
+
+#[cfg(feature = "embedded-dict")]
+#[test]
+fn default_english_solves_a_cryptogram() {
+    let solver = Solver::default_english();
+    let phrase = Phrase::from_str("MAXNASAXLFYA YDFVCAXN ASAXQKJBFP").unwrap();
+    let solutions: Vec<_> = solver.solve(&phrase).collect();
+    assert_eq!(solutions, ["perseverance conquers everything"]);
+}
+
+/// Computes a word's letter pattern the straightforward way, with a hash map from letters to
+/// symbols.
+fn reference_pattern(word: &str) -> String {
+    let mut symbols = HashMap::new();
+    word.bytes()
+        .map(|u| {
+            let next = symbols.len() as u8;
+            (b'a' + *symbols.entry(u).or_insert(next)) as char
+        })
+        .collect()
+}
+
+#[test]
+fn patterns_match_a_hash_map_reference() {
+    let words = [
+        "a",
+        "aa",
+        "cat",
+        "tot",
+        "noon",
+        "hello",
+        "banana",
+        "mississippi",
+        "abracadabra",
+        "bookkeeper",
+        "strength",
+        "xyzzy",
+    ];
+    let solver = Solver::from_dictionary(&words);
+
+    for word in &words {
+        let explanation = solver.explain_word(word, 0);
+        assert_eq!(explanation.pattern, reference_pattern(word), "{}", word);
+
+        let same_pattern = words
+            .iter()
+            .filter(|other| reference_pattern(other) == explanation.pattern)
+            .count();
+        assert_eq!(explanation.candidate_count, same_pattern, "{}", word);
+    }
+}
+
+#[test]
+fn estimated_solution_count_bounds_the_true_count() {
+    for text in &[
+        "XLI GEX WEX",
+        "LIPPS ASVPH",
+        "EPP MW AIPP",
+        "NIJBVO OBJO YAVWJB ABVB",
+        "MAXNASAXLFYA YDFVCAXN ASAXQKJBFP",
+    ] {
+        let phrase = Phrase::from_str(text).unwrap();
+        let count = enable1().solution_count(&phrase);
+        let estimate = enable1().estimate_solution_count(&phrase);
+        assert!(count <= estimate, "{}: {} > {}", text, count, estimate);
+        assert!(count > 0, "{}", text);
+    }
+}
+
+#[test]
+fn joint_solutions_share_one_key() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog", "act"]);
+
+    // Alone, each phrase has several solutions, but only one key fits both.
+    let a = Phrase::from_str("xyz").unwrap();
+    let b = Phrase::from_str("zyw").unwrap();
+    assert!(solver.solve(&a).count() > 1);
+    let mut joint = solver.solve_jointly(&[&a, &b]);
+    joint.sort();
+    assert_eq!(
+        joint,
+        [vec!["cat", "tag"], vec!["cot", "tog"], vec!["dog", "got"],]
+    );
+
+    // Each phrase is solvable alone, but no two words begin with the same two letters.
+    let c = Phrase::from_str("xyw").unwrap();
+    assert!(solver.solve(&c).count() > 0);
+    assert!(solver.solve_jointly(&[&a, &c]).is_empty());
+}
+
+#[test]
+fn theme_words_rank_first() {
+    let solver = Solver::from_dictionary(&["the", "cat", "cot", "cut"]);
+    let phrase = Phrase::from_str("xli gex").unwrap();
+    let order = |ranked: Vec<(String, f64)>| -> Vec<String> {
+        ranked.into_iter().map(|(solution, _)| solution).collect()
+    };
+
+    let plain = order(solver.solve_ranked(&phrase, &rank::LetterFrequencies));
+    assert_ne!(plain[0], "the cut");
+
+    let theme: HashSet<_> = vec!["cut"].into_iter().collect();
+    let themed = order(solver.solve_ranked_with_theme(&phrase, &theme));
+    assert_eq!(themed[0], "the cut");
+    assert_eq!(themed[1..], plain[..2]);
+
+    // Solutions without theme words are kept, just lower.
+    assert_eq!(themed.len(), plain.len());
+}
+
+#[test]
+fn short_word_bonus_favors_common_short_words() {
+    // All three are words, but only one is a word anyone would use.
+    let solver = Solver::from_dictionary(&["ae", "oe", "at"]);
+    let phrase = Phrase::from_str("xy").unwrap();
+    let order = |ranked: Vec<(String, f64)>| -> Vec<String> {
+        ranked.into_iter().map(|(solution, _)| solution).collect()
+    };
+
+    let plain = order(solver.solve_ranked_by(&phrase, rank::score));
+    assert_eq!(plain, ["ae", "oe", "at"]);
+
+    let biased = order(solver.solve_ranked_by(&phrase, |solution| {
+        rank::score(solution) + rank::short_word_bonus(solution)
+    }));
+    assert_eq!(biased, ["at", "ae", "oe"]);
+}
+
+#[test]
+fn budget_limits_solutions_reproducibly() {
+    let words = ["cat", "cot", "dog", "got", "tag", "tog", "cog", "dot"];
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let all = Solver::from_dictionary(&words).solve(&phrase).count();
+
+    let mut counts = Vec::new();
+    for budget in 0..40 {
+        // Separately built solvers, so that nothing carries over between runs.
+        let solve = || -> Vec<_> {
+            Solver::from_dictionary(&words)
+                .solve_with_budget(&phrase, budget)
+                .collect()
+        };
+        let solutions = solve();
+        assert_eq!(solutions, solve(), "budget {}", budget);
+        counts.push(solutions.len());
+    }
+
+    assert_eq!(counts[0], 0);
+    assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(counts.iter().any(|&count| 0 < count && count < all));
+    assert_eq!(counts.last(), Some(&all));
+}
+
+#[test]
+fn solutions_by_coverage_count_hints_and_the_forced_last_pair() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog"]);
+    let phrase = Phrase::from_str("gex").unwrap();
+
+    // Every key determines three letters, so the more common plaintext letters go first.
+    assert_eq!(
+        solver.solve_by_coverage(&phrase, &HashMap::new()),
+        [
+            ("cat".to_string(), 3),
+            ("cot".to_string(), 3),
+            ("dog".to_string(), 3)
+        ]
+    );
+
+    let hints: HashMap<_, _> = vec![(b'q', b'z')].into_iter().collect();
+    assert_eq!(solver.solve_by_coverage(&phrase, &hints)[0].1, 4);
+
+    // With the phrase and these, 25 letters are known, and the last cipher letter can only be
+    // the last plaintext letter.
+    let hints: HashMap<_, _> = (b'a'..=b'z')
+        .filter(|u| !b"gexz".contains(u))
+        .zip((b'a'..=b'z').filter(|u| !b"caot".contains(u)))
+        .collect();
+    let solutions = solver.solve_by_coverage(&phrase, &hints);
+    assert!(!solutions.is_empty());
+    assert!(solutions.iter().all(|(_, coverage)| *coverage == 26));
+}
+
+#[test]
+fn bounded_solutions_keep_only_the_best() {
+    const LIMIT: usize = 10;
+
+    let phrase = Phrase::from_str("XLI GEX").unwrap();
+    let mut bounded = rank::BoundedSolutions::new(LIMIT, rank::score);
+    let mut found = 0;
+    enable1().for_each_mapping(&phrase, &HashMap::new(), |mapping| {
+        bounded.push(phrase.render(&mapping));
+        found += 1;
+    });
+    let (kept, truncated) = bounded.finish();
+
+    assert!(found > LIMIT);
+    assert!(truncated);
+    assert!(!kept.is_empty() && kept.len() <= LIMIT);
+
+    // The best solutions always survive being cut down.
+    let all = rank::rank_by(enable1().solve(&phrase), rank::score);
+    assert_eq!(kept[..LIMIT / 2], all[..LIMIT / 2]);
+
+    let mut bounded = rank::BoundedSolutions::new(LIMIT, rank::score);
+    bounded.push("the cat".to_string());
+    assert!(!bounded.finish().1);
+
+    // The smallest bound still keeps the best solution.
+    let mut bounded = rank::BoundedSolutions::new(2, rank::score);
+    for solution in vec!["xq zv", "the cat", "qz jx"].into_iter() {
+        bounded.push(solution.to_string());
+    }
+    assert_eq!(bounded.finish().0[0].0, "the cat");
+}
+
+#[test]
+#[should_panic]
+fn bounded_solutions_need_room_for_two() {
+    rank::BoundedSolutions::new(1, rank::score);
+}
+
+#[test]
+fn solving_words_matches_solving_the_phrase() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog", "a"]);
+    let phrase = Phrase::from_str("xyz zyw y xyz").unwrap();
+    let sorted = |mut mappings: Vec<HashMap<u8, u8>>| {
+        mappings.sort_by_key(|mapping| phrase.render(mapping));
+        mappings
+    };
+
+    let words = sorted(solver.solve_words(&["xyz", "zyw", "y", "xyz"]));
+    assert!(!words.is_empty());
+    assert_eq!(words, sorted(solver.solve_mappings(&phrase)));
+}
+
+#[test]
+fn solutions_nearest_the_reference_key_come_first() {
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+
+    // Only x and y are known from the reference.
+    let mut reference = [b'.'; 26];
+    reference[(b'x' - b'a') as usize] = b'd';
+    reference[(b'y' - b'a') as usize] = b'o';
+
+    assert_eq!(
+        solver.solve_near_key(&phrase, &reference),
+        [
+            ("dog got".to_string(), 0),
+            ("cot tog".to_string(), 1),
+            ("cat tag".to_string(), 2),
+        ]
+    );
+}
+
+#[test]
+fn lazily_ranked_solutions_start_near_the_top() {
+    let phrase = Phrase::from_str("XLI GEX").unwrap();
+    let (first, _) = enable1().solve_ranked_lazily(&phrase).next().unwrap();
+
+    let ranked = enable1().solve_ranked(&phrase, &rank::LetterFrequencies);
+    let position = ranked
+        .iter()
+        .position(|(solution, _)| *solution == first)
+        .unwrap();
+    assert!(position < 10, "{} of {}", position, ranked.len());
+
+    // Laziness changes only the order.
+    let solver = Solver::from_dictionary(&["cat", "cot", "dog", "got", "tag", "tog"]);
+    let phrase = Phrase::from_str("xyz zyw").unwrap();
+    let mut lazy: Vec<_> = solver
+        .solve_ranked_lazily(&phrase)
+        .map(|(solution, _)| solution)
+        .collect();
+    lazy.sort();
+    let mut all: Vec<_> = solver.solve(&phrase).collect();
+    all.sort();
+    assert_eq!(lazy, all);
+}
+
+#[test]
+fn distinct_letters_are_counted_once() {
+    let count = |text: &str| Phrase::from_str(text).unwrap().distinct_letter_count();
+
+    assert_eq!(count("XLI GEX"), 5);
+    assert_eq!(count("mississippi"), 4);
+    assert_eq!(count("the quick brown fox jumps over the lazy dog"), 26);
+    assert_eq!(count(""), 0);
+}
+
+#[test]
+fn first_word_cap_limits_each_first_word() {
+    let phrase = Phrase::from_str("XLI GEX").unwrap();
+
+    for cap in 1..4 {
+        let solutions = enable1().solve_with_first_word_cap(&phrase, &HashMap::new(), cap);
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for solution in &solutions {
+            *counts
+                .entry(solution.split(' ').next().unwrap())
+                .or_default() += 1;
+        }
+
+        assert!(counts.len() > 1);
+        assert!(counts.values().all(|&count| count <= cap), "cap {}", cap);
+        assert!(counts.values().any(|&count| count == cap), "cap {}", cap);
+    }
+}
+
+#[test]
+fn words_of_more_than_26_distinct_bytes_are_not_rejected() {
+    // Nothing assumes an alphabet of 26 letters: words may use any bytes, and patterns and
+    // mappings have room for every one of them.
+    let word = "0123456789abcdefghijklmnopqrstuvwxyz";
+    let encrypted_word: String = word.chars().rev().collect();
+    let solver = Solver::from_dictionary(&[word]);
+
+    let mappings = solver.solve_words(&[&encrypted_word]);
+    assert_eq!(mappings.len(), 1);
+    assert_eq!(mappings[0].len(), 36);
+    assert_eq!(solver.explain_word(&encrypted_word, 0).candidate_count, 1);
+
+    // Phrases are made of letters alone, folded to lowercase, so they never have more than 26.
+    let phrase = Phrase::from_str("abcdefghijklmnopqrstuvwxyz ABCDEFGHIJKLMNOPQRSTUVWXYZ").unwrap();
+    assert_eq!(phrase.distinct_letter_count(), 26);
+    assert!(Phrase::from_str("x1").is_err());
+}